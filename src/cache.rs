@@ -0,0 +1,262 @@
+use crate::errors::CloudError;
+use crate::java::JavaRuntime;
+use crate::loader::build_loader;
+use crate::plugins::PLUGINS_DIR;
+use crate::{AppState, Daemon, config};
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{read_dir, remove_dir_all, remove_file, symlink_metadata};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+use tokio::time::interval;
+
+const JDK_DIR: &str = ".jdk";
+const VERSIONS_DIR: &str = "versions";
+
+/// Suffixes of the files written next to a loader artifact, stripped to find what they belong to.
+const ARTIFACT_SUFFIXES: [&str; 5] = [".part", ".jar", ".build", ".json", ".tar.gz"];
+
+#[derive(Serialize)]
+pub struct CachedArtifact {
+    path: String,
+    size_bytes: u64,
+    referenced: bool,
+}
+
+#[derive(Serialize)]
+pub struct CacheReport {
+    loaders: Vec<CachedArtifact>,
+    jdks: Vec<CachedArtifact>,
+    plugins: Vec<CachedArtifact>,
+    total_bytes: u64,
+    reclaimable_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct PruneReport {
+    removed: Vec<String>,
+    freed_bytes: u64,
+}
+
+#[derive(Deserialize)]
+pub struct PruneQuery {
+    min_age_secs: Option<u64>,
+}
+
+/// Cache entries still needed by a registered instance, as `versions/<loader>/<stem>`,
+/// `.jdk/<folder>` and `plugins/<source>/<project>/<stem>`.
+struct References {
+    loaders: HashSet<PathBuf>,
+    jdks: HashSet<PathBuf>,
+    plugins: HashSet<PathBuf>,
+}
+
+async fn collect_references(daemon: &Arc<Mutex<Daemon>>) -> References {
+    let mut references = References {
+        loaders: HashSet::new(),
+        jdks: HashSet::new(),
+        plugins: HashSet::new(),
+    };
+
+    let guard = daemon.lock().await;
+    for inst in &guard.server_list {
+        let inst_guard = inst.lock().await;
+        let loader = build_loader(&inst_guard.loader);
+        references.loaders.insert(
+            Path::new(VERSIONS_DIR)
+                .join(loader.name())
+                .join(format!("{}-{}", loader.name(), loader.artifact_id())),
+        );
+        if let JavaRuntime::Managed(jdk) = inst_guard.java_runtime() {
+            references
+                .jdks
+                .insert(Path::new(JDK_DIR).join(jdk.folder_name()));
+        }
+        for plugin in &inst_guard.plugins {
+            references
+                .plugins
+                .insert(artifact_stem(Path::new(&plugin.file)));
+        }
+    }
+    references
+}
+
+fn artifact_stem(path: &Path) -> PathBuf {
+    let Some(mut name) = path.file_name().and_then(|n| n.to_str()) else {
+        return path.to_path_buf();
+    };
+    if !path.is_dir() {
+        while let Some(stripped) = ARTIFACT_SUFFIXES
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix))
+        {
+            name = stripped;
+        }
+    }
+    path.with_file_name(name)
+}
+
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    read_dir(dir)
+        .map(|entries| entries.filter_map(Result::ok).map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+/// Every cached loader artifact, one level below `versions/<loader>/`.
+fn loader_entries() -> Vec<PathBuf> {
+    entries(Path::new(VERSIONS_DIR))
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .flat_map(|dir| entries(&dir))
+        .collect()
+}
+
+/// Every cached plugin jar, two levels below `plugins/<source>/`.
+fn plugin_entries() -> Vec<PathBuf> {
+    entries(Path::new(PLUGINS_DIR))
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .flat_map(|dir| entries(&dir))
+        .filter(|path| path.is_dir())
+        .flat_map(|dir| entries(&dir))
+        .collect()
+}
+
+fn describe(paths: Vec<PathBuf>, referenced: &HashSet<PathBuf>) -> Vec<CachedArtifact> {
+    let mut artifacts = paths
+        .into_iter()
+        .map(|path| CachedArtifact {
+            referenced: referenced.contains(&artifact_stem(&path)),
+            size_bytes: disk_usage(&path),
+            path: path.to_string_lossy().into_owned(),
+        })
+        .collect::<Vec<_>>();
+    artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+    artifacts
+}
+
+pub async fn cache_report(daemon: &Arc<Mutex<Daemon>>) -> CacheReport {
+    let references = collect_references(daemon).await;
+    let loaders = describe(loader_entries(), &references.loaders);
+    let jdks = describe(entries(Path::new(JDK_DIR)), &references.jdks);
+    let plugins = describe(plugin_entries(), &references.plugins);
+
+    let all = loaders.iter().chain(jdks.iter()).chain(plugins.iter());
+    let total_bytes = all.clone().map(|a| a.size_bytes).sum();
+    let reclaimable_bytes = all.filter(|a| !a.referenced).map(|a| a.size_bytes).sum();
+
+    CacheReport {
+        loaders,
+        jdks,
+        plugins,
+        total_bytes,
+        reclaimable_bytes,
+    }
+}
+
+fn is_older_than(path: &Path, min_age: Duration) -> bool {
+    symlink_metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= min_age)
+}
+
+/// Removes cached artifacts no registered instance uses, as long as they are older than `min_age`.
+pub async fn prune(daemon: &Arc<Mutex<Daemon>>, min_age: Duration) -> Result<PruneReport, CloudError> {
+    let references = collect_references(daemon).await;
+    let candidates = loader_entries()
+        .into_iter()
+        .filter(|path| !references.loaders.contains(&artifact_stem(path)))
+        .chain(
+            entries(Path::new(JDK_DIR))
+                .into_iter()
+                .filter(|path| !references.jdks.contains(&artifact_stem(path))),
+        )
+        .chain(
+            plugin_entries()
+                .into_iter()
+                .filter(|path| !references.plugins.contains(&artifact_stem(path))),
+        );
+
+    let mut report = PruneReport {
+        removed: Vec::new(),
+        freed_bytes: 0,
+    };
+    for path in candidates {
+        if !is_older_than(&path, min_age) {
+            continue;
+        }
+        let size = disk_usage(&path);
+        if path.is_dir() {
+            remove_dir_all(&path).map_err(|_| CloudError::FileError)?;
+        } else {
+            remove_file(&path).map_err(|_| CloudError::FileError)?;
+        }
+        report.freed_bytes += size;
+        report.removed.push(path.to_string_lossy().into_owned());
+    }
+    Ok(report)
+}
+
+pub fn start_prune_schedule(daemon: Arc<Mutex<Daemon>>) {
+    let cache_config = &config::get().cache;
+    let Some(every) = cache_config.prune_interval_secs else {
+        return;
+    };
+    let retention = Duration::from_secs(cache_config.retention_secs);
+
+    tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(every));
+        loop {
+            interval.tick().await;
+            match prune(&daemon, retention).await {
+                Ok(report) if !report.removed.is_empty() => println!(
+                    "Pruned {} cached artifacts, freed {} bytes",
+                    report.removed.len(),
+                    report.freed_bytes
+                ),
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to prune cache: {:?}", e),
+            }
+        }
+    });
+}
+
+pub async fn get_cache(State(state): State<AppState>) -> impl IntoResponse {
+    Json(cache_report(&state.daemon).await)
+}
+
+pub async fn prune_cache(
+    State(state): State<AppState>,
+    Query(query): Query<PruneQuery>,
+) -> impl IntoResponse {
+    let min_age = Duration::from_secs(query.min_age_secs.unwrap_or(0));
+    match prune(&state.daemon, min_age).await {
+        Ok(report) => Json(report).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Could not prune the cache").into_response(),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::errors::CloudError;
+use crate::loader::{LOADER_NAMES, LoaderBuild, catalog_loader};
+use crate::minecraft_version::MinecraftVersion;
+use axum::Json;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Serialize;
+use std::fs::{read_dir, read_to_string};
+
+#[derive(Serialize)]
+struct LoaderSummary {
+    name: &'static str,
+    cached: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct VersionEntry {
+    version: String,
+    stable: bool,
+    cached: bool,
+}
+
+#[derive(Serialize)]
+struct BuildEntry {
+    build: String,
+    stable: bool,
+    cached: bool,
+}
+
+/// Artifact ids present in `versions/<loader>`: cached jars and installer-based trees.
+fn cached_artifacts(loader: &str) -> Vec<String> {
+    let Ok(entries) = read_dir(format!("versions/{}", loader)) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}-", loader);
+
+    let mut cached = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let stem = file_name.strip_prefix(&prefix)?;
+            if entry.path().is_dir() {
+                Some(stem.to_string())
+            } else {
+                stem.strip_suffix(".jar").map(str::to_string)
+            }
+        })
+        .collect::<Vec<_>>();
+    cached.sort();
+    cached
+}
+
+fn is_version_cached(cached: &[String], version: &str) -> bool {
+    let prefix = format!("{}-", version);
+    cached
+        .iter()
+        .any(|id| id == version || id.starts_with(&prefix))
+}
+
+fn is_build_cached(loader: &str, cached: &[String], version: &str, build: &str) -> bool {
+    let pinned = format!("{}-{}", version, build);
+    let prefix = format!("{}-", pinned);
+    cached.iter().any(|id| {
+        id == &pinned
+            || id.starts_with(&prefix)
+            || (id == version
+                && read_to_string(format!("versions/{}/{}-{}.build", loader, loader, id))
+                    .is_ok_and(|installed| installed == build))
+    })
+}
+
+fn is_stable(version: &str) -> bool {
+    match MinecraftVersion::parse(version) {
+        Ok(version) => version.is_stable(),
+        Err(_) => !version.to_ascii_lowercase().contains("snapshot"),
+    }
+}
+
+fn catalog_error(error: CloudError) -> (StatusCode, &'static str) {
+    match error {
+        CloudError::Unsupported => (
+            StatusCode::NOT_IMPLEMENTED,
+            "This loader does not publish a catalog",
+        ),
+        CloudError::UnknownVersion => (StatusCode::NOT_FOUND, "Unknown version for this loader"),
+        _ => (StatusCode::BAD_GATEWAY, "Could not query the loader upstream"),
+    }
+}
+
+pub async fn list_loaders() -> impl IntoResponse {
+    let loaders = LOADER_NAMES
+        .iter()
+        .map(|name| LoaderSummary {
+            name,
+            cached: cached_artifacts(name),
+        })
+        .collect::<Vec<_>>();
+    Json(loaders)
+}
+
+pub async fn list_versions(Path(name): Path<String>) -> impl IntoResponse {
+    let Some(loader) = catalog_loader(&name) else {
+        return (StatusCode::NOT_FOUND, "Unknown loader").into_response();
+    };
+
+    match loader.upstream_versions().await {
+        Ok(versions) => {
+            let cached = cached_artifacts(loader.name());
+            let versions = versions
+                .into_iter()
+                .map(|version| VersionEntry {
+                    stable: is_stable(&version),
+                    cached: is_version_cached(&cached, &version),
+                    version,
+                })
+                .collect::<Vec<_>>();
+            Json(versions).into_response()
+        }
+        Err(e) => catalog_error(e).into_response(),
+    }
+}
+
+pub async fn list_builds(Path((name, version)): Path<(String, String)>) -> impl IntoResponse {
+    let Some(loader) = catalog_loader(&name) else {
+        return (StatusCode::NOT_FOUND, "Unknown loader").into_response();
+    };
+
+    match loader.list_builds(&version).await {
+        Ok(builds) => {
+            let cached = cached_artifacts(loader.name());
+            let builds = builds
+                .into_iter()
+                .map(|LoaderBuild { build, stable }| BuildEntry {
+                    cached: is_build_cached(loader.name(), &cached, &version, &build),
+                    build,
+                    stable,
+                })
+                .collect::<Vec<_>>();
+            Json(builds).into_response()
+        }
+        Err(e) => catalog_error(e).into_response(),
+    }
+}
@@ -0,0 +1,178 @@
+use crate::errors::CloudError;
+use crate::java::provider::JdkVendor;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const CONFIG_PATH: &str = "aesir.json";
+
+static CONFIG: OnceLock<DaemonConfig> = OnceLock::new();
+
+/// Daemon-wide settings read from `aesir.json`, every field falls back to its default.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct DaemonConfig {
+    pub cache: CacheConfig,
+    pub upstreams: UpstreamConfig,
+    pub offline: OfflineConfig,
+    pub proxy: ProxyConfig,
+    pub java: JavaConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Prune unreferenced loader jars, JDKs and plugins on this interval, never when unset.
+    pub prune_interval_secs: Option<u64>,
+    /// Scheduled prunes keep unreferenced artifacts younger than this.
+    pub retention_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            prune_interval_secs: None,
+            retention_secs: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Base URLs of every service artifacts are resolved from, e.g. to point at an internal stand-in.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct UpstreamConfig {
+    pub papermc: String,
+    pub purpur: String,
+    pub fabric_meta: String,
+    pub mojang_meta: String,
+    pub forge_files: String,
+    pub forge_maven: String,
+    pub neoforge_maven: String,
+    pub github_api: String,
+    pub oracle_jdk: String,
+    pub adoptium_api: String,
+    pub modrinth_api: String,
+    pub hangar_api: String,
+    pub quilt_meta: String,
+    pub quilt_maven: String,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            papermc: "https://api.papermc.io".to_string(),
+            purpur: "https://api.purpurmc.org".to_string(),
+            fabric_meta: "https://meta.fabricmc.net".to_string(),
+            mojang_meta: "https://piston-meta.mojang.com".to_string(),
+            forge_files: "https://files.minecraftforge.net".to_string(),
+            forge_maven: "https://maven.minecraftforge.net".to_string(),
+            neoforge_maven: "https://maven.neoforged.net".to_string(),
+            github_api: "https://api.github.com".to_string(),
+            oracle_jdk: "https://download.oracle.com".to_string(),
+            adoptium_api: "https://api.adoptium.net".to_string(),
+            modrinth_api: "https://api.modrinth.com".to_string(),
+            hangar_api: "https://hangar.papermc.io".to_string(),
+            quilt_meta: "https://meta.quiltmc.org".to_string(),
+            quilt_maven: "https://maven.quiltmc.org".to_string(),
+        }
+    }
+}
+
+impl UpstreamConfig {
+    fn normalize(&mut self) {
+        for url in [
+            &mut self.papermc,
+            &mut self.purpur,
+            &mut self.fabric_meta,
+            &mut self.mojang_meta,
+            &mut self.forge_files,
+            &mut self.forge_maven,
+            &mut self.neoforge_maven,
+            &mut self.github_api,
+            &mut self.oracle_jdk,
+            &mut self.adoptium_api,
+            &mut self.modrinth_api,
+            &mut self.hangar_api,
+            &mut self.quilt_meta,
+            &mut self.quilt_maven,
+        ] {
+            let trimmed = url.trim_end_matches('/').len();
+            url.truncate(trimmed);
+        }
+    }
+}
+
+/// In offline mode nothing is fetched from the internet: every URL is looked up in the mirror
+/// under `<host>/<path>`, the layout `wget --mirror` produces.
+///
+/// In a local directory, an API response whose path also holds downloads is stored as
+/// `<host>/<path>/index.json`, e.g. `api.papermc.io/v2/projects/paper/index.json` next to
+/// `api.papermc.io/v2/projects/paper/versions/`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct OfflineConfig {
+    pub enabled: bool,
+    /// A local directory or the base URL of an internal HTTP mirror.
+    pub mirror: Option<String>,
+}
+
+/// How backends learn the real address and profile of players connecting through a proxy.
+///
+/// Only backends behind a proxy are configured for it, standalone ones keep their own settings.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardingMode {
+    /// Velocity's own forwarding, authenticated by a shared secret.
+    #[default]
+    Modern,
+    /// BungeeCord-style forwarding, for backends older than 1.13.
+    Legacy,
+    /// Backends are left in online mode and configured by hand.
+    None,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub forwarding: ForwardingMode,
+    /// Shared between proxies and backends, generated into `forwarding.secret` when unset.
+    pub forwarding_secret: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct JavaConfig {
+    /// Used by instances that do not pick a vendor themselves.
+    pub vendor: JdkVendor,
+    /// Run on a compatible JDK found in `JAVA_HOME` or `search_paths` rather than downloading one.
+    pub prefer_system: bool,
+    /// JDK homes, or directories of them, scanned for system JDKs.
+    pub search_paths: Vec<String>,
+    /// Exact release of the daemon's vendor installed for a Java version, e.g.
+    /// `{"21": "jdk-21.0.5+11"}`, so every node runs the same build.
+    pub releases: HashMap<u32, String>,
+}
+
+impl Default for JavaConfig {
+    fn default() -> Self {
+        Self {
+            vendor: JdkVendor::default(),
+            prefer_system: true,
+            search_paths: vec!["/usr/lib/jvm".to_string()],
+            releases: HashMap::new(),
+        }
+    }
+}
+
+pub fn load() -> Result<(), CloudError> {
+    let mut config: DaemonConfig = match std::fs::read_to_string(CONFIG_PATH) {
+        Ok(content) => serde_json::from_str(&content).map_err(|_| CloudError::JSONError)?,
+        Err(_) => DaemonConfig::default(),
+    };
+    config.upstreams.normalize();
+    CONFIG.set(config).map_err(|_| CloudError::FatalError)
+}
+
+pub fn get() -> &'static DaemonConfig {
+    CONFIG.get_or_init(DaemonConfig::default)
+}
@@ -9,4 +9,14 @@ pub enum CloudError {
     HTTPError,
     JSONError,
     NoStableBuild,
+    NoArtifactForVersion,
+    ChecksumMismatch,
+    InstallerError,
+    RoleMismatch,
+    InvalidArtifactName,
+    UnknownVersion,
+    Unsupported,
+    NotMirrored,
+    Incompatible,
+    UnsupportedPlatform,
 }
@@ -1,26 +1,352 @@
+use crate::config;
 use crate::errors::CloudError;
 
 use futures_util::StreamExt;
-use tokio::fs::File;
+use reqwest::{Client, StatusCode, Url};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, copy, read, read_to_string, remove_file, rename};
 use tokio::io::AsyncWriteExt;
 
-pub async fn download_file(url: &str, path: &str) -> Result<(), CloudError> {
-    let response = reqwest::get(url)
+const MIRROR_INDEX: &str = "index.json";
+const USER_AGENT: &str = concat!("AesirCloud/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Clone, Debug)]
+pub enum Checksum {
+    Sha1(String),
+    Sha256(String),
+    Sha512(String),
+}
+
+enum ChecksumHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Checksum {
+    fn hasher(&self) -> ChecksumHasher {
+        match self {
+            Checksum::Sha1(_) => ChecksumHasher::Sha1(Sha1::new()),
+            Checksum::Sha256(_) => ChecksumHasher::Sha256(Sha256::new()),
+            Checksum::Sha512(_) => ChecksumHasher::Sha512(Sha512::new()),
+        }
+    }
+
+    fn expected(&self) -> &str {
+        match self {
+            Checksum::Sha1(expected) | Checksum::Sha256(expected) | Checksum::Sha512(expected) => {
+                expected
+            }
+        }
+    }
+}
+
+impl ChecksumHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            ChecksumHasher::Sha1(hasher) => hasher.update(data),
+            ChecksumHasher::Sha256(hasher) => hasher.update(data),
+            ChecksumHasher::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            ChecksumHasher::Sha1(hasher) => to_hex(&hasher.finalize()),
+            ChecksumHasher::Sha256(hasher) => to_hex(&hasher.finalize()),
+            ChecksumHasher::Sha512(hasher) => to_hex(&hasher.finalize()),
+        }
+    }
+}
+
+pub fn http_client() -> Result<Client, CloudError> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|_| CloudError::HTTPError)
+}
+
+/// Where a URL is actually read from, which differs from the URL itself in offline mode.
+enum Location {
+    Remote(String),
+    Mirrored(PathBuf),
+}
+
+fn locate(url: &str) -> Result<Location, CloudError> {
+    let offline = &config::get().offline;
+    if !offline.enabled {
+        return Ok(Location::Remote(url.to_string()));
+    }
+    let mirror = offline
+        .mirror
+        .as_deref()
+        .ok_or(CloudError::NotMirrored)?
+        .trim_end_matches('/');
+    let is_remote_mirror = mirror.starts_with("http://") || mirror.starts_with("https://");
+    if is_remote_mirror && url.starts_with(mirror) {
+        return Ok(Location::Remote(url.to_string()));
+    }
+
+    let parsed = Url::parse(url).map_err(|_| CloudError::NotMirrored)?;
+    let mut relative = format!(
+        "{}{}",
+        parsed.host_str().ok_or(CloudError::NotMirrored)?,
+        parsed.path()
+    );
+    if let Some(query) = parsed.query() {
+        relative.push('?');
+        relative.push_str(query);
+    }
+
+    if is_remote_mirror {
+        Ok(Location::Remote(format!("{}/{}", mirror, relative)))
+    } else {
+        Ok(Location::Mirrored(Path::new(mirror).join(relative)))
+    }
+}
+
+/// API endpoints such as `/v2/projects/paper` are also parents of download paths, a local
+/// mirror keeps their response in an `index.json` inside the directory.
+fn mirrored_document(path: PathBuf) -> PathBuf {
+    if path.is_dir() {
+        path.join(MIRROR_INDEX)
+    } else {
+        path
+    }
+}
+
+pub async fn fetch_text(url: &str) -> Result<String, CloudError> {
+    match locate(url)? {
+        Location::Remote(url) => http_client()?
+            .get(url)
+            .send()
+            .await
+            .map_err(|_| CloudError::HTTPError)?
+            .error_for_status()
+            .map_err(|_| CloudError::HTTPError)?
+            .text()
+            .await
+            .map_err(|_| CloudError::HTTPError),
+        Location::Mirrored(path) => read_to_string(mirrored_document(path))
+            .await
+            .map_err(|_| CloudError::NotMirrored),
+    }
+}
+
+pub async fn fetch_json(url: &str) -> Result<serde_json::Value, CloudError> {
+    serde_json::from_str(&fetch_text(url).await?).map_err(|_| CloudError::JSONError)
+}
+
+/// One page of a paginated JSON API and the URL of the next, taken from the `Link` header
+/// as GitHub sends it. Mirrors only hold the first page.
+pub async fn fetch_json_page(url: &str) -> Result<(serde_json::Value, Option<String>), CloudError> {
+    match locate(url)? {
+        Location::Remote(url) => {
+            let resp = http_client()?
+                .get(url)
+                .send()
+                .await
+                .map_err(|_| CloudError::HTTPError)?
+                .error_for_status()
+                .map_err(|_| CloudError::HTTPError)?;
+            let next = resp
+                .headers()
+                .get("link")
+                .and_then(|link| link.to_str().ok())
+                .and_then(next_page);
+            let text = resp.text().await.map_err(|_| CloudError::HTTPError)?;
+            let json = serde_json::from_str(&text).map_err(|_| CloudError::JSONError)?;
+            Ok((json, next))
+        }
+        Location::Mirrored(_) => Ok((fetch_json(url).await?, None)),
+    }
+}
+
+/// The `rel="next"` target of a header such as `<https://...&page=2>; rel="next", <...>; rel="last"`.
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|entry| {
+        let (target, params) = entry.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                target
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// Like `fetch_text`, but nothing published at `url` is `None` rather than an error.
+pub async fn fetch_text_if_found(url: &str) -> Result<Option<String>, CloudError> {
+    match locate(url)? {
+        Location::Remote(url) => {
+            let resp = http_client()?
+                .get(url)
+                .send()
+                .await
+                .map_err(|_| CloudError::HTTPError)?;
+            if resp.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            resp.error_for_status()
+                .map_err(|_| CloudError::HTTPError)?
+                .text()
+                .await
+                .map(Some)
+                .map_err(|_| CloudError::HTTPError)
+        }
+        Location::Mirrored(path) => Ok(read_to_string(mirrored_document(path)).await.ok()),
+    }
+}
+
+pub async fn fetch_json_if_found(url: &str) -> Result<Option<serde_json::Value>, CloudError> {
+    match fetch_text_if_found(url).await? {
+        Some(text) => serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|_| CloudError::JSONError),
+        None => Ok(None),
+    }
+}
+
+/// Whether something is published at `url`, without downloading it.
+pub async fn exists(url: &str) -> Result<bool, CloudError> {
+    match locate(url)? {
+        Location::Remote(url) => {
+            let resp = http_client()?
+                .head(url)
+                .send()
+                .await
+                .map_err(|_| CloudError::HTTPError)?;
+            if resp.status() == StatusCode::NOT_FOUND {
+                return Ok(false);
+            }
+            resp.error_for_status().map_err(|_| CloudError::HTTPError)?;
+            Ok(true)
+        }
+        Location::Mirrored(path) => Ok(path.is_file()),
+    }
+}
+
+/// Downloads `url` next to `path` and only moves it into place once the checksum, if any, matches.
+pub async fn download_file(
+    url: &str,
+    path: &str,
+    checksum: Option<&Checksum>,
+) -> Result<(), CloudError> {
+    let url = match locate(url)? {
+        Location::Remote(url) => url,
+        Location::Mirrored(source) => {
+            if !source.is_file() {
+                return Err(CloudError::NotMirrored);
+            }
+            return copy_file(&source.to_string_lossy(), path, checksum).await;
+        }
+    };
+
+    let response = http_client()?
+        .get(&url)
+        .send()
         .await
+        .map_err(|_| CloudError::DownloadError)?
+        .error_for_status()
         .map_err(|_| CloudError::DownloadError)?;
 
-    let mut file = File::create(path)
+    let temp_path = format!("{}.part", path);
+    let mut file = File::create(&temp_path)
         .await
         .map_err(|_| CloudError::FileError)?;
 
+    let mut hasher = checksum.map(Checksum::hasher);
     let mut stream = response.bytes_stream();
 
     while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|_| CloudError::DownloadError)?;
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(_) => {
+                let _ = remove_file(&temp_path).await;
+                return Err(CloudError::DownloadError);
+            }
+        };
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&chunk);
+        }
         file.write_all(&chunk)
             .await
             .map_err(|_| CloudError::FileError)?;
     }
+    file.flush().await.map_err(|_| CloudError::FileError)?;
+    drop(file);
+
+    finish_verified(&temp_path, path, checksum, hasher, &url).await
+}
+
+/// SHA-256 of a file already on disk, as recorded in lockfiles.
+pub async fn file_sha256(path: &str) -> Result<String, CloudError> {
+    let content = read(path).await.map_err(|_| CloudError::FileError)?;
+    Ok(sha256_hex(&content))
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    to_hex(&Sha256::digest(content))
+}
+
+/// The first 16 hex digits of a key's SHA-256, to name cache entries after their origin.
+pub fn short_hash(key: &str) -> String {
+    to_hex(&Sha256::digest(key.as_bytes())[..8])
+}
+
+/// Copies a local artifact into the cache with the same verification as a download.
+pub async fn copy_file(
+    source: &str,
+    path: &str,
+    checksum: Option<&Checksum>,
+) -> Result<(), CloudError> {
+    let temp_path = format!("{}.part", path);
+    copy(source, &temp_path)
+        .await
+        .map_err(|_| CloudError::FileError)?;
+
+    let hasher = match checksum {
+        Some(checksum) => {
+            let content = read(&temp_path).await.map_err(|_| CloudError::FileError)?;
+            let mut hasher = checksum.hasher();
+            hasher.update(&content);
+            Some(hasher)
+        }
+        None => None,
+    };
+
+    finish_verified(&temp_path, path, checksum, hasher, source).await
+}
+
+async fn finish_verified(
+    temp_path: &str,
+    path: &str,
+    checksum: Option<&Checksum>,
+    hasher: Option<ChecksumHasher>,
+    origin: &str,
+) -> Result<(), CloudError> {
+    if let (Some(checksum), Some(hasher)) = (checksum, hasher) {
+        let digest = hasher.finalize();
+        if !checksum.expected().eq_ignore_ascii_case(&digest) {
+            eprintln!("Checksum mismatch for {}: got {}", origin, digest);
+            remove_file(temp_path)
+                .await
+                .map_err(|_| CloudError::FileError)?;
+            return Err(CloudError::ChecksumMismatch);
+        }
+    }
+
+    rename(temp_path, path)
+        .await
+        .map_err(|_| CloudError::FileError)
+}
 
-    Ok(())
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
@@ -1,4 +1,5 @@
 use crate::instance::Instance;
+use crate::proxy::sync_proxies;
 use crate::screen_manager::stop_screen;
 use crate::{AppState, Daemon};
 use axum::extract::{Path, State};
@@ -59,7 +60,7 @@ pub async fn heartbeat_handler(
 	}
 }
 
-fn now() -> u64 {
+pub fn now() -> u64 {
 	SystemTime::now()
 		.duration_since(UNIX_EPOCH)
 		.expect("Time went backward ??")
@@ -76,25 +77,34 @@ pub async fn start_heartbeat_check(instance: Arc<Mutex<Instance>>, daemon: Arc<M
 		let mut interval = interval(Duration::from_secs(10));
 		loop {
 			interval.tick().await;
-			let alive = {
+			let (alive, stopped) = {
 				let inst_guard = instance.lock().await;
-				is_server_alive(&inst_guard)
+				(is_server_alive(&inst_guard), !inst_guard.heartbeat_started)
 			};
 
+			// Stopped on purpose, a later start spawns its own check.
+			if stopped {
+				break
+			}
+
 			if !alive {
 				let server_id = {
 					let inst_guard = instance.lock().await;
 					inst_guard.server_id.clone()
 				};
 				println!("Server {} seems down, unregistering...", server_id);
-				let guard = daemon.lock().await;
-				if let Some(inst_arc) = guard.get_instance(&server_id).await {
+				let inst_arc = {
+					let guard = daemon.lock().await;
+					guard.get_instance(&server_id).await
+				};
+				if let Some(inst_arc) = inst_arc {
 					if let Err(_) = stop_screen(inst_arc.clone()).await {
 						eprintln!("Error stopping server {}", server_id)
 					}
 					let mut inst_guard = inst_arc.lock().await;
 					inst_guard.started = false
 				}
+				sync_proxies(daemon.clone()).await;
 				break
 			}
 		}
@@ -0,0 +1,72 @@
+use crate::errors::CloudError;
+use crate::file_downloader::{Checksum, download_file};
+use crate::java::JavaRuntime;
+use crate::loader::LoaderBackend;
+use std::collections::HashMap;
+use std::fs::create_dir_all;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// One lock per artifact, so concurrent starts wait on a single download instead of racing on it.
+static IN_FLIGHT: LazyLock<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
+
+fn loader_key(loader: &dyn LoaderBackend) -> String {
+    format!("loader:{}/{}", loader.name(), loader.artifact_id())
+}
+
+async fn lock_artifact(key: String) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+        in_flight.entry(key).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+/// Installs the loader unless present, returns whether a download happened.
+pub async fn ensure_loader(loader: &dyn LoaderBackend) -> Result<bool, CloudError> {
+    if loader.is_installed() {
+        return Ok(false);
+    }
+    let _guard = lock_artifact(loader_key(loader)).await;
+    if loader.is_installed() {
+        return Ok(false);
+    }
+    loader.install().await?;
+    Ok(true)
+}
+
+/// System JDKs are used as they are, managed ones are downloaded unless present.
+pub async fn ensure_java(runtime: &JavaRuntime) -> Result<(), CloudError> {
+    let JavaRuntime::Managed(jdk) = runtime else {
+        return Ok(());
+    };
+    if jdk.is_installed() {
+        return Ok(());
+    }
+    let _guard = lock_artifact(format!("jdk:{}", jdk.folder_name())).await;
+    if jdk.is_installed() {
+        return Ok(());
+    }
+    jdk.install().await
+}
+
+/// Downloads a shared file such as a plugin unless it is already cached.
+pub async fn ensure_cached(
+    path: &str,
+    url: &str,
+    checksum: Option<&Checksum>,
+) -> Result<(), CloudError> {
+    if Path::new(path).exists() {
+        return Ok(());
+    }
+    let _guard = lock_artifact(format!("file:{}", path)).await;
+    if Path::new(path).exists() {
+        return Ok(());
+    }
+    if let Some(parent) = Path::new(path).parent() {
+        create_dir_all(parent).map_err(|_| CloudError::FileError)?;
+    }
+    download_file(url, path, checksum).await
+}
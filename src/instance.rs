@@ -1,5 +1,5 @@
 use crate::errors::CloudError;
-use crate::screen_manager::{start_screen, stop_screen};
+use crate::screen_manager::{launch_command, start_screen, stop_screen};
 use crate::{AppState, Daemon};
 use axum::Json;
 use axum::extract::{Path, State};
@@ -11,28 +11,111 @@ use std::fs::File;
 use std::io::Write;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use crate::installs::ensure_loader;
+use crate::java::{JavaRequirement, JavaRuntime};
+use crate::java::provider::JdkVendor;
 use crate::loader::build_loader;
 use crate::loader::config::LoaderConfig;
+use crate::plugins::{InstalledPlugin, sync_plugins, write_lockfile};
+use crate::proxy::{is_proxied, lists, sync_proxies, write_initial_config};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceRole {
+    #[default]
+    Backend,
+    Proxy,
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Instance {
     pub server_id: String,
     pub server_name: String,
     pub is_persistent: bool,
+    /// Upgraded together, and a proxy in a group only routes players to backends of that group.
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub role: InstanceRole,
+    /// Set on backends only reached through a proxy the daemon does not manage, so they are
+    /// configured for forwarding like those behind a managed proxy, but keep listening on
+    /// every address.
+    #[serde(default)]
+    pub behind_proxy: bool,
     pub loader: LoaderConfig,
+    #[serde(default)]
+    pub loader_build: Option<String>,
     pub port: u16,
     pub max_player: u16,
+    /// Heap given to the JVM, `-Xms` and `-Xmx` are left to the JVM when unset.
+    #[serde(default)]
+    pub memory_mb: Option<u32>,
+    /// Extra JVM flags, replacing the loader defaults that set the same option.
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    #[serde(default)]
+    pub skip_default_jvm_args: bool,
+    /// Overrides the daemon's JDK vendor, the instance then never runs on a system JDK.
+    #[serde(default)]
+    pub jdk_vendor: Option<JdkVendor>,
+    /// Exact JDK release such as `jdk-21.0.5+11`, overriding the daemon's pin.
+    #[serde(default)]
+    pub jdk_release: Option<String>,
+    /// Full version of the JDK the instance last started on.
+    #[serde(default)]
+    pub jdk_version: Option<String>,
+    #[serde(default)]
+    pub plugins: Vec<InstalledPlugin>,
     pub started: bool,
     pub heartbeat_started: bool,
     pub last_heartbeat: u64,
 }
 
+impl Instance {
+    pub fn directory(&self) -> String {
+        format!(
+            "running/{}/{}",
+            if self.is_persistent {
+                "static"
+            } else {
+                "disposable"
+            },
+            self.server_id
+        )
+    }
+
+    pub fn java_runtime(&self) -> JavaRuntime {
+        let loader = build_loader(&self.loader);
+        JavaRuntime::select(
+            loader.java_requirement(),
+            loader.java_version(),
+            self.jdk_vendor,
+            self.jdk_release.as_deref(),
+        )
+    }
+}
+
 pub async fn create_instance(
     State(state): State<AppState>,
     Json(request): Json<Instance>,
 ) -> impl IntoResponse {
     match register_instance(state.daemon.clone(), request).await {
         Ok(_) => (StatusCode::CREATED, "Instance successfully registered").into_response(),
+        Err(CloudError::UnknownVersion) => (
+            StatusCode::BAD_REQUEST,
+            "This version is not available for this loader",
+        )
+            .into_response(),
+        Err(CloudError::InvalidArtifactName) | Err(CloudError::NoArtifactForVersion) => (
+            StatusCode::BAD_REQUEST,
+            "Could not find the artifact for this loader",
+        )
+            .into_response(),
+        Err(CloudError::Unsupported) => (
+            StatusCode::BAD_REQUEST,
+            "This loader does not support this Minecraft version",
+        )
+            .into_response(),
         Err(_) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             "Could not register instance",
@@ -41,34 +124,40 @@ pub async fn create_instance(
     }
 }
 
-async fn register_instance(
+pub async fn register_instance(
     daemon: Arc<Mutex<Daemon>>,
     instance: Instance,
 ) -> Result<(), CloudError> {
+    let loader = build_loader(&instance.loader);
+    loader.validate().await?;
+
     let mut guard = daemon.lock().await;
 
+    let mut proxied = instance.behind_proxy;
     for inst in &guard.server_list {
         let inst_guard = inst.lock().await;
         if inst_guard.server_id == instance.server_id {
             return Err(CloudError::InstanceAlreadyExists);
         }
+        proxied |= lists(&inst_guard, &instance);
     }
 
-    let dir_path = format!(
-        "running/{}/{}",
-        if instance.is_persistent {
-            "static"
-        } else {
-            "disposable"
-        },
-        instance.server_id
-    );
+    if loader.is_proxy() != (instance.role == InstanceRole::Proxy) {
+        return Err(CloudError::RoleMismatch);
+    }
+
+    let dir_path = instance.directory();
 
     if let Err(e) = fs::create_dir_all(&dir_path) {
         eprintln!("Failed to create directory {}: {}", dir_path, e);
         return Err(CloudError::FileError);
     }
 
+    if let Some(content_dir) = loader.content_dir() {
+        let content_path = format!("{}/{}", dir_path, content_dir);
+        fs::create_dir_all(&content_path).map_err(|_| CloudError::FileError)?;
+    }
+
     let eula_path = format!("{}/{}", dir_path, "eula.txt");
     let mut eula = File::create(eula_path).map_err(|_| CloudError::FileError)?;
     eula.write("eula=true".as_bytes()).map_err(|_| CloudError::FileError)?;
@@ -77,9 +166,16 @@ async fn register_instance(
     let mut config = File::create(config_path).map_err(|_| CloudError::FileError)?;
     config.write(format!("server_id={}", instance.server_id).as_bytes()).map_err(|_| CloudError::FileError)?;
 
-    let properties_path = format!("{}/{}", dir_path, "server.properties");
-    let mut properties = File::create(properties_path).map_err(|_| CloudError::FileError)?;
-    properties.write(format!("max-players={}\nserver-port={}", instance.max_player, instance.port).as_bytes()).map_err(|_| CloudError::FileError)?;
+    match instance.role {
+        InstanceRole::Backend => {
+            let properties_path = format!("{}/{}", dir_path, "server.properties");
+            let mut properties = File::create(properties_path).map_err(|_| CloudError::FileError)?;
+            properties.write(format!("max-players={}\nserver-port={}", instance.max_player, instance.port).as_bytes()).map_err(|_| CloudError::FileError)?;
+        }
+        InstanceRole::Proxy => write_initial_config(&instance)?,
+    }
+    loader.write_config_files(&instance, proxied)?;
+    write_lockfile(&instance)?;
 
     guard.server_list.push(Arc::new(Mutex::new(instance)));
     Ok(())
@@ -95,32 +191,61 @@ pub async fn start_instance_status(
     };
 
     if let Some(instance_arc) = instance_opt {
-        start_instance(instance_arc).await.into_response()
+        let response = start_instance(&state.daemon, instance_arc).await;
+        if response.0.is_success() {
+            sync_proxies(state.daemon.clone()).await;
+        }
+        response.into_response()
     } else {
         (StatusCode::NOT_FOUND, "Could not find this instance").into_response()
     }
 }
 
-async fn start_instance(inst_arc: Arc<Mutex<Instance>>) -> (StatusCode, String) {
+pub async fn start_instance(
+    daemon: &Arc<Mutex<Daemon>>,
+    inst_arc: Arc<Mutex<Instance>>,
+) -> (StatusCode, String) {
+    let proxied = is_proxied(daemon, &inst_arc).await;
     let mut instance = inst_arc.lock().await;
     let loader = build_loader(&instance.loader);
 
-    if !loader.is_installed() {
-        if let Err(_) = loader.install().await {
+    match ensure_loader(loader.as_ref()).await {
+        Ok(true) => println!("Downloaded new minecraft loader"),
+        Ok(false) => {}
+        Err(e) => {
+            eprintln!("Failed to install {} loader: {:?}", loader.name(), e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Error while retrieving the minecraft loader !".to_string(),
             );
         }
-        println!("Downloaded new minecraft loader");
     }
+    instance.loader_build = loader.installed_build();
 
-    if let Err(_) = start_screen(instance.clone()).await {
+    if let Err(e) = sync_plugins(&mut instance).await {
+        eprintln!("Failed to sync plugins of {}: {:?}", instance.server_id, e);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "Error while starting screen".to_string(),
+            "Error while syncing plugins with the lockfile".to_string(),
         );
     }
+
+    match start_screen(instance.clone(), proxied).await {
+        Ok(()) => {}
+        Err(CloudError::UnsupportedPlatform) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "No JDK build exists for this host's architecture and C library".to_string(),
+            );
+        }
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error while starting screen".to_string(),
+            );
+        }
+    }
+    instance.jdk_version = instance.java_runtime().full_version();
     instance.started = true;
     (StatusCode::OK, "Server started".to_string())
 }
@@ -135,12 +260,43 @@ pub async fn stop_instance(
     };
 
     if let Some(inst_arc) = instance_opt {
-        if let Err(_) = stop_screen(inst_arc).await {
+        if stop_screen(inst_arc.clone()).await.is_err() {
             (StatusCode::INTERNAL_SERVER_ERROR, "Error in 'screen' command !").into_response()
         } else {
+            inst_arc.lock().await.started = false;
+            sync_proxies(state.daemon.clone()).await;
             (StatusCode::OK, "Successfully stopped screen").into_response()
         }
     } else {
         (StatusCode::NOT_FOUND, "Could not find this instance").into_response()
     }
 }
+
+#[derive(Serialize)]
+struct LaunchCommand {
+    directory: String,
+    java: JavaRequirement,
+    command: Vec<String>,
+}
+
+pub async fn get_launch_command(
+    State(state): State<AppState>,
+    Path(server_id): Path<String>,
+) -> impl IntoResponse {
+    let instance_opt = {
+        let guard = state.daemon.lock().await;
+        guard.get_instance(&server_id).await.clone()
+    };
+
+    if let Some(inst_arc) = instance_opt {
+        let instance = inst_arc.lock().await;
+        Json(LaunchCommand {
+            directory: instance.directory(),
+            java: build_loader(&instance.loader).java_requirement(),
+            command: launch_command(&instance),
+        })
+        .into_response()
+    } else {
+        (StatusCode::NOT_FOUND, "Could not find this instance").into_response()
+    }
+}
@@ -0,0 +1,159 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::{Checksum, download_file};
+use crate::java::JavaVersion;
+use crate::java::platform::Platform;
+use crate::java::provider::{JdkRelease, JdkVendor, build_provider};
+use crate::java::system::release_version;
+use flate2::read::GzDecoder;
+use std::fs::{File, create_dir_all, read_dir, read_to_string, remove_dir_all, rename, write};
+use std::path::Path;
+use tar::Archive;
+use tokio::fs::remove_file;
+
+const JDK_DIR: &str = ".jdk";
+const STAGING_SUFFIX: &str = ".staging";
+const RELEASE_RECORD: &str = "aesir-jdk.json";
+
+/// One vendor's build of a Java version, installed under `.jdk/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jdk {
+    pub version: JavaVersion,
+    pub vendor: JdkVendor,
+    /// Exact release such as `jdk-21.0.5+11`, the newest build of `version` when unset.
+    pub release: Option<String>,
+}
+
+impl Jdk {
+    /// Uses the daemon's vendor unless one is given, and then the release it pins if any.
+    pub fn new(version: JavaVersion, vendor: Option<JdkVendor>, release: Option<String>) -> Self {
+        let java = &config::get().java;
+        let release = match (release, vendor) {
+            (Some(release), _) => Some(release),
+            (None, None) => java.releases.get(&version.major()).cloned(),
+            (None, Some(_)) => None,
+        };
+        Self {
+            version,
+            vendor: vendor.unwrap_or(java.vendor),
+            release,
+        }
+    }
+
+    /// Oracle JDKs keep the `jdk21` folders they were installed to before vendors were selectable.
+    pub fn folder_name(&self) -> String {
+        match (&self.release, self.vendor) {
+            (Some(release), vendor) => format!("{}-{}", vendor.name(), release),
+            (None, JdkVendor::Oracle) => format!("jdk{}", self.version.major()),
+            (None, vendor) => format!("{}-{}", vendor.name(), self.version.major()),
+        }
+    }
+
+    pub fn local_path(&self) -> String {
+        format!("{}/{}", JDK_DIR, self.folder_name())
+    }
+
+    pub fn java_path(&self) -> String {
+        format!("{}/bin/java", self.local_path())
+    }
+
+    /// What was installed, as recorded inside the JDK.
+    pub fn installed_release(&self) -> Option<JdkRelease> {
+        let content = read_to_string(format!("{}/{}", self.local_path(), RELEASE_RECORD)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn installed_version(&self) -> Option<String> {
+        self.installed_release()?.version
+    }
+
+    /// Unpacks into a staging directory and moves the JDK into place with a single rename,
+    /// so an interrupted install never looks installed.
+    pub async fn install(&self) -> Result<(), CloudError> {
+        let mut release = build_provider(self.vendor)
+            .resolve(self.version, self.release.as_deref(), Platform::detect()?)
+            .await?;
+
+        let local = self.local_path();
+        let folder = self.folder_name();
+        create_dir_all(JDK_DIR).map_err(|_| CloudError::FileError)?;
+
+        let archive_path = format!("{}/{}.tar.gz", JDK_DIR, folder);
+        let checksum = Checksum::Sha256(release.sha256.clone());
+        download_file(&release.url, &archive_path, Some(&checksum)).await?;
+
+        let staging = format!("{}/{}{}", JDK_DIR, folder, STAGING_SUFFIX);
+        if Path::new(&staging).exists() {
+            remove_dir_all(&staging).map_err(|_| CloudError::FileError)?;
+        }
+        create_dir_all(&staging).map_err(|_| CloudError::FileError)?;
+
+        let tar_gz = File::open(&archive_path).map_err(|_| CloudError::FileError)?;
+        let decompressor = GzDecoder::new(tar_gz);
+        let mut archive = Archive::new(decompressor);
+        archive.unpack(&staging).map_err(|_| CloudError::FileError)?;
+
+        // Archives hold a single top-level folder, e.g. `jdk-21.0.5+11/`.
+        let extracted = read_dir(&staging)
+            .map_err(|_| CloudError::FileError)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .find(|path| path.join("bin/java").is_file())
+            .ok_or(CloudError::FileError)?;
+
+        if release.version.is_none() {
+            release.version = release_version(&extracted);
+        }
+        let record = serde_json::to_string_pretty(&release).map_err(|_| CloudError::JSONError)?;
+        write(extracted.join(RELEASE_RECORD), record).map_err(|_| CloudError::FileError)?;
+
+        if Path::new(&local).exists() {
+            remove_dir_all(&local).map_err(|_| CloudError::FileError)?;
+        }
+        rename(&extracted, &local).map_err(|_| CloudError::FileError)?;
+
+        remove_dir_all(&staging).map_err(|_| CloudError::FileError)?;
+        remove_file(&archive_path)
+            .await
+            .map_err(|_| CloudError::FileError)?;
+
+        Ok(())
+    }
+
+    pub fn is_installed(&self) -> bool {
+        Path::new(&self.java_path()).exists()
+    }
+}
+
+/// Removes what interrupted installs leave in `.jdk/`: staging directories, archives and JDK
+/// folders without a `java` binary. Returns how many entries were removed.
+pub fn clean_partial_installs() -> usize {
+    let Ok(entries) = read_dir(JDK_DIR) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let result = if path.is_dir() {
+            let partial = name.ends_with(STAGING_SUFFIX)
+                || !path.join("bin/java").is_file()
+                || path.join("temp_extract").exists();
+            if !partial {
+                continue;
+            }
+            remove_dir_all(&path)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tar.gz.part") {
+            std::fs::remove_file(&path)
+        } else {
+            continue;
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => eprintln!("Failed to remove partial JDK install {}: {}", path.display(), e),
+        }
+    }
+    removed
+}
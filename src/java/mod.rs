@@ -0,0 +1,207 @@
+pub mod jdk;
+pub mod platform;
+pub mod provider;
+pub mod system;
+
+use crate::config;
+use crate::java::jdk::Jdk;
+use crate::java::provider::{JdkVendor, release_major};
+use crate::java::system::{SystemJdk, system_jdks};
+use crate::minecraft_version::MinecraftVersion;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A Java feature release, identified by its major version: 8, 11, 17, 21, 25...
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawJavaVersion", into = "u32")]
+pub struct JavaVersion(u32);
+
+/// Accepts the `J21` names written by earlier versions as well as plain major numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawJavaVersion {
+    Major(u32),
+    Name(String),
+}
+
+/// Java versions a server runs on, `max` being unbounded when nothing newer is known to break.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JavaRequirement {
+    pub min: JavaVersion,
+    pub max: Option<JavaVersion>,
+}
+
+/// The JDK an instance runs on, either found on the host or downloaded into `.jdk/`.
+#[derive(Debug, Clone)]
+pub enum JavaRuntime {
+    System(SystemJdk),
+    Managed(Jdk),
+}
+
+/// Long-term support releases, which vendors keep publishing builds of.
+const LTS_RELEASES: [u32; 5] = [8, 11, 17, 21, 25];
+
+/// First release of each range and the Java versions it supports, oldest first.
+///
+/// The minimum is what Mojang ships the range with, the maximum the newest LTS known to run
+/// the range along with its mod loaders.
+const RELEASE_REQUIREMENTS: [(&str, u32, Option<u32>); 6] = [
+    ("1.0", 8, Some(8)),
+    ("1.13-pre1", 8, Some(11)),
+    ("1.17-pre1", 16, Some(17)),
+    ("1.18-pre1", 17, Some(21)),
+    ("1.20.5-pre1", 21, Some(25)),
+    ("26.1-snapshot-1", 25, None),
+];
+
+/// The same ranges for weekly snapshots, keyed by the first snapshot of each.
+const SNAPSHOT_REQUIREMENTS: [(&str, u32, Option<u32>); 5] = [
+    ("09w01a", 8, Some(8)),
+    ("17w43a", 8, Some(11)),
+    ("21w19a", 16, Some(17)),
+    ("21w37a", 17, Some(21)),
+    ("24w14a", 21, Some(25)),
+];
+
+impl JavaVersion {
+    pub const fn new(major: u32) -> Self {
+        Self(major)
+    }
+
+    pub fn major(&self) -> u32 {
+        self.0
+    }
+
+    /// Java 8 and older were versioned `1.x`, `1.8` is read as 8.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().trim_start_matches(['J', 'j']);
+        let raw = raw.strip_prefix("1.").unwrap_or(raw);
+        let major = raw.split(['.', '_', '+', '-']).next()?.parse().ok()?;
+        (major >= 8).then_some(Self(major))
+    }
+}
+
+impl JavaRuntime {
+    /// Prefers a compatible system JDK, closest to `preferred`, over downloading `preferred`.
+    ///
+    /// Picking a vendor or pinning a release opts out of system JDKs, as theirs are unknown.
+    /// A pinned release runs on the Java version it belongs to.
+    pub fn select(
+        requirement: JavaRequirement,
+        preferred: JavaVersion,
+        vendor: Option<JdkVendor>,
+        release: Option<&str>,
+    ) -> Self {
+        let version = release.and_then(release_major).unwrap_or(preferred);
+        let managed = Jdk::new(version, vendor, release.map(str::to_string));
+        if vendor.is_none() && managed.release.is_none() && config::get().java.prefer_system {
+            let system = system_jdks()
+                .iter()
+                .filter(|jdk| requirement.allows(jdk.version))
+                .min_by_key(|jdk| jdk.version.0.abs_diff(preferred.0));
+            if let Some(jdk) = system {
+                return JavaRuntime::System(jdk.clone());
+            }
+        }
+        JavaRuntime::Managed(managed)
+    }
+
+    /// Path of the `java` binary, managed JDKs being resolved from the daemon's `root`.
+    pub fn java_path(&self, root: &str) -> String {
+        match self {
+            JavaRuntime::System(jdk) => jdk.java_path(),
+            JavaRuntime::Managed(jdk) => format!("{}/{}", root, jdk.java_path()),
+        }
+    }
+
+    pub fn full_version(&self) -> Option<String> {
+        match self {
+            JavaRuntime::System(jdk) => Some(jdk.full_version.clone()),
+            JavaRuntime::Managed(jdk) => jdk.installed_version(),
+        }
+    }
+}
+
+impl JavaRequirement {
+    pub fn allows(&self, version: JavaVersion) -> bool {
+        version >= self.min && self.max.is_none_or(|max| version <= max)
+    }
+
+    /// The oldest LTS allowed, e.g. 17 rather than the end-of-life 16 for 1.17, or `min` when
+    /// no LTS is in range. `max` only bounds which system or pinned JDKs are accepted, as older
+    /// servers may refuse Java releases newer than they were tested on.
+    pub fn preferred(&self) -> JavaVersion {
+        LTS_RELEASES
+            .iter()
+            .map(|major| JavaVersion(*major))
+            .find(|version| self.allows(*version))
+            .unwrap_or(self.min)
+    }
+
+    pub fn exactly(version: JavaVersion) -> Self {
+        Self {
+            min: version,
+            max: Some(version),
+        }
+    }
+
+    pub fn at_least(version: JavaVersion) -> Self {
+        Self {
+            min: version,
+            max: None,
+        }
+    }
+
+    /// Looks the version up in the range tables, anything older than them gets the first range.
+    pub fn for_minecraft(version: &MinecraftVersion) -> Self {
+        let table: &[(&str, u32, Option<u32>)] = match version.release() {
+            Some(_) => &RELEASE_REQUIREMENTS,
+            None => &SNAPSHOT_REQUIREMENTS,
+        };
+        let (_, min, max) = *table
+            .iter()
+            .rev()
+            .find(|(first, _, _)| {
+                MinecraftVersion::parse(first).is_ok_and(|first| first <= *version)
+            })
+            .unwrap_or(&table[0]);
+        Self {
+            min: JavaVersion(min),
+            max: max.map(JavaVersion),
+        }
+    }
+
+    /// Raises the minimum, e.g. to what an upstream manifest asks for.
+    pub fn with_min(self, min: JavaVersion) -> Self {
+        Self {
+            min: min.max(self.min),
+            max: self.max.filter(|max| *max >= min),
+        }
+    }
+}
+
+impl TryFrom<RawJavaVersion> for JavaVersion {
+    type Error = String;
+
+    fn try_from(raw: RawJavaVersion) -> Result<Self, Self::Error> {
+        match raw {
+            RawJavaVersion::Major(major) if major >= 8 => Ok(Self(major)),
+            RawJavaVersion::Major(major) => Err(format!("Java {} is not supported", major)),
+            RawJavaVersion::Name(name) => {
+                Self::parse(&name).ok_or_else(|| format!("'{}' is not a Java version", name))
+            }
+        }
+    }
+}
+
+impl From<JavaVersion> for u32 {
+    fn from(version: JavaVersion) -> Self {
+        version.0
+    }
+}
+
+impl fmt::Display for JavaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Java {}", self.0)
+    }
+}
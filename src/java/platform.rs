@@ -0,0 +1,107 @@
+use crate::errors::CloudError;
+use std::fs::{read, read_dir};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X64,
+    Aarch64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libc {
+    Glibc,
+    Musl,
+}
+
+/// What a downloaded JDK has to be built for to run on this host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    pub arch: Arch,
+    pub libc: Libc,
+}
+
+impl Arch {
+    /// As JDK vendors name it in their archives.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arch::X64 => "x64",
+            Arch::Aarch64 => "aarch64",
+        }
+    }
+}
+
+impl Platform {
+    pub fn detect() -> Result<Self, CloudError> {
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => Arch::X64,
+            "aarch64" => Arch::Aarch64,
+            _ => return Err(CloudError::UnsupportedPlatform),
+        };
+        Ok(Self {
+            arch,
+            libc: detect_libc(),
+        })
+    }
+}
+
+/// The dynamic loader `/bin/sh` is linked against tells the C library apart best, as hosts
+/// may carry the other one's loader too: glibc hosts with the musl package, or Alpine hosts
+/// with gcompat's `ld-linux-*`. Without it, Alpine is recognised by its release file and
+/// other hosts by the loaders in `/lib`.
+fn detect_libc() -> Libc {
+    if let Some(interpreter) = interpreter("/bin/sh") {
+        if interpreter.contains("ld-musl-") {
+            return Libc::Musl;
+        }
+        if interpreter.contains("ld-linux-") {
+            return Libc::Glibc;
+        }
+    }
+    if Path::new("/etc/alpine-release").exists() {
+        Libc::Musl
+    } else if has_loader("ld-linux-") {
+        Libc::Glibc
+    } else if has_loader("ld-musl-") {
+        Libc::Musl
+    } else {
+        Libc::Glibc
+    }
+}
+
+fn has_loader(prefix: &str) -> bool {
+    ["/lib", "/lib64"].iter().any(|dir| {
+        read_dir(dir).is_ok_and(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        })
+    })
+}
+
+/// The `PT_INTERP` program header of a 64-bit little-endian ELF binary.
+fn interpreter(path: &str) -> Option<String> {
+    const PT_INTERP: u32 = 3;
+    let elf = read(path).ok()?;
+    if elf.get(..6)? != b"\x7fELF\x02\x01" {
+        return None;
+    }
+    let u16_at = |at: usize| Some(u16::from_le_bytes(elf.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(elf.get(at..at + 4)?.try_into().ok()?));
+    let u64_at = |at: usize| Some(u64::from_le_bytes(elf.get(at..at + 8)?.try_into().ok()?));
+
+    let table = u64_at(0x20)? as usize;
+    let entry_size = u16_at(0x36)? as usize;
+    let entries = u16_at(0x38)? as usize;
+    let header = (0..entries)
+        .map(|i| table + i * entry_size)
+        .find(|header| u32_at(*header) == Some(PT_INTERP))?;
+    let offset = u64_at(header + 8)? as usize;
+    let size = u64_at(header + 32)? as usize;
+    let name = elf.get(offset..offset + size)?;
+    Some(
+        String::from_utf8_lossy(name)
+            .trim_end_matches('\0')
+            .to_string(),
+    )
+}
@@ -0,0 +1,232 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::{exists, fetch_json, fetch_json_if_found, fetch_text_if_found};
+use crate::java::JavaVersion;
+use crate::java::platform::{Arch, Libc, Platform};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Who builds the JDKs instances run on.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum JdkVendor {
+    /// Eclipse Temurin, resolved to an exact release through the Adoptium API.
+    #[default]
+    Temurin,
+    /// Oracle's `latest` archive of each feature release.
+    Oracle,
+}
+
+/// A JDK archive resolved by a provider, kept next to the install to record what it is.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JdkRelease {
+    pub vendor: JdkVendor,
+    /// The full version, e.g. `jdk-21.0.5+11`, unknown until installed for some vendors.
+    pub version: Option<String>,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[async_trait]
+pub trait JdkProvider: Send + Sync {
+    /// Picks the archive of `release` for the host's platform, or of the newest build of the
+    /// feature release when no exact release is pinned.
+    async fn resolve(
+        &self,
+        version: JavaVersion,
+        release: Option<&str>,
+        platform: Platform,
+    ) -> Result<JdkRelease, CloudError>;
+}
+
+pub struct TemurinProvider;
+
+pub struct OracleProvider;
+
+pub fn build_provider(vendor: JdkVendor) -> Box<dyn JdkProvider> {
+    match vendor {
+        JdkVendor::Temurin => Box::new(TemurinProvider),
+        JdkVendor::Oracle => Box::new(OracleProvider),
+    }
+}
+
+/// The feature release a release name such as `jdk-21.0.5+11` or `jdk8u432-b06` belongs to.
+pub fn release_major(release: &str) -> Option<JavaVersion> {
+    let rest = release.strip_prefix("jdk")?.trim_start_matches('-');
+    let major = rest
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+    JavaVersion::parse(&major)
+}
+
+impl JdkVendor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JdkVendor::Temurin => "temurin",
+            JdkVendor::Oracle => "oracle",
+        }
+    }
+}
+
+/// Tells a Java version Adoptium does not publish apart from one missing for this platform.
+async fn temurin_missing(version: JavaVersion) -> CloudError {
+    let url = format!(
+        "{}/v3/info/available_releases",
+        config::get().upstreams.adoptium_api
+    );
+    match fetch_json(&url).await {
+        Ok(resp) => {
+            let published = resp["available_releases"]
+                .as_array()
+                .is_some_and(|releases| {
+                    releases
+                        .iter()
+                        .any(|r| r.as_u64() == Some(version.major() as u64))
+                });
+            if published {
+                CloudError::UnsupportedPlatform
+            } else {
+                CloudError::NoArtifactForVersion
+            }
+        }
+        Err(e) => e,
+    }
+}
+
+/// Oracle builds every version for x64, so a missing archive elsewhere is only a platform gap
+/// when the x64 one exists.
+async fn oracle_missing(url: &str, platform: Platform) -> CloudError {
+    if platform.arch == Arch::X64 {
+        return CloudError::NoArtifactForVersion;
+    }
+    let x64_url = url.replace(
+        &format!("_linux-{}_", platform.arch.name()),
+        &format!("_linux-{}_", Arch::X64.name()),
+    );
+    match exists(&x64_url).await {
+        Ok(true) => CloudError::UnsupportedPlatform,
+        Ok(false) => CloudError::NoArtifactForVersion,
+        Err(e) => e,
+    }
+}
+
+#[async_trait]
+impl JdkProvider for TemurinProvider {
+    /// musl builds are published for Alpine, under their own operating system.
+    async fn resolve(
+        &self,
+        version: JavaVersion,
+        release: Option<&str>,
+        platform: Platform,
+    ) -> Result<JdkRelease, CloudError> {
+        let os = match platform.libc {
+            Libc::Glibc => "linux",
+            Libc::Musl => "alpine-linux",
+        };
+        let filters = format!(
+            "architecture={}&image_type=jdk&os={}&vendor=eclipse",
+            platform.arch.name(),
+            os
+        );
+        let api = &config::get().upstreams.adoptium_api;
+
+        // The latest endpoint lists one asset per build, a release lists its binaries.
+        let (release_name, binary) = match release {
+            Some(release) => {
+                let resp = fetch_json_if_found(&format!(
+                    "{}/v3/assets/release_name/eclipse/{}?{}",
+                    api,
+                    release.replace('+', "%2B"),
+                    filters
+                ))
+                .await?
+                .ok_or(CloudError::NoArtifactForVersion)?;
+                let binary = resp["binaries"]
+                    .as_array()
+                    .and_then(|binaries| binaries.first())
+                    .cloned()
+                    .ok_or(CloudError::UnsupportedPlatform)?;
+                (resp["release_name"].as_str().map(str::to_string), binary)
+            }
+            None => {
+                let resp = fetch_json_if_found(&format!(
+                    "{}/v3/assets/latest/{}/hotspot?{}",
+                    api,
+                    version.major(),
+                    filters
+                ))
+                .await?;
+                let Some(asset) = resp.as_ref().and_then(|r| r.as_array()?.first()) else {
+                    return Err(temurin_missing(version).await);
+                };
+                (
+                    asset["release_name"].as_str().map(str::to_string),
+                    asset["binary"].clone(),
+                )
+            }
+        };
+        let package = &binary["package"];
+
+        Ok(JdkRelease {
+            vendor: JdkVendor::Temurin,
+            version: release_name,
+            url: package["link"]
+                .as_str()
+                .ok_or(CloudError::JSONError)?
+                .to_string(),
+            sha256: package["checksum"]
+                .as_str()
+                .ok_or(CloudError::JSONError)?
+                .to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl JdkProvider for OracleProvider {
+    /// Oracle publishes the SHA-256 of every archive next to it, but not which build `latest` is.
+    /// Exact releases such as `jdk-21.0.5` come from its archive.
+    async fn resolve(
+        &self,
+        version: JavaVersion,
+        release: Option<&str>,
+        platform: Platform,
+    ) -> Result<JdkRelease, CloudError> {
+        // Oracle only builds against glibc.
+        if platform.libc == Libc::Musl {
+            return Err(CloudError::UnsupportedPlatform);
+        }
+        let url = match release {
+            Some(release) => format!(
+                "{}/java/{}/archive/{}_linux-{}_bin.tar.gz",
+                config::get().upstreams.oracle_jdk,
+                version.major(),
+                release,
+                platform.arch.name()
+            ),
+            None => format!(
+                "{}/java/{}/latest/jdk-{}_linux-{}_bin.tar.gz",
+                config::get().upstreams.oracle_jdk,
+                version.major(),
+                version.major(),
+                platform.arch.name()
+            ),
+        };
+        let Some(sha256) = fetch_text_if_found(&format!("{}.sha256", url)).await? else {
+            return Err(oracle_missing(&url, platform).await);
+        };
+        let sha256 = sha256
+            .split_whitespace()
+            .next()
+            .ok_or(CloudError::DownloadError)?
+            .to_string();
+
+        Ok(JdkRelease {
+            vendor: JdkVendor::Oracle,
+            version: release.map(str::to_string),
+            url,
+            sha256,
+        })
+    }
+}
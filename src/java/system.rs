@@ -0,0 +1,94 @@
+use crate::config;
+use crate::java::JavaVersion;
+use std::collections::HashSet;
+use std::fs::{canonicalize, read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// A JDK installed outside the daemon, e.g. by the system's package manager.
+#[derive(Debug, Clone)]
+pub struct SystemJdk {
+    pub home: PathBuf,
+    pub version: JavaVersion,
+    /// As reported by the JDK, e.g. `21.0.5` or `1.8.0_412`.
+    pub full_version: String,
+}
+
+impl SystemJdk {
+    pub fn java_path(&self) -> String {
+        self.home.join("bin/java").to_string_lossy().into_owned()
+    }
+
+    fn probe(home: &Path) -> Option<Self> {
+        if !home.join("bin/java").is_file() {
+            return None;
+        }
+        let full_version = release_version(home).or_else(|| reported_version(home))?;
+        Some(Self {
+            home: home.to_path_buf(),
+            version: JavaVersion::parse(&full_version)?,
+            full_version,
+        })
+    }
+}
+
+/// `JAVA_VERSION` from the `release` file at the root of the JDK.
+pub fn release_version(home: &Path) -> Option<String> {
+    let content = read_to_string(home.join("release")).ok()?;
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix("JAVA_VERSION=")?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// The quoted version of `java -version`, which prints to stderr.
+fn reported_version(home: &Path) -> Option<String> {
+    let output = Command::new(home.join("bin/java"))
+        .arg("-version")
+        .output()
+        .ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_line = stderr.lines().next()?;
+    Some(first_line.split('"').nth(1)?.to_string())
+}
+
+static SYSTEM_JDKS: OnceLock<Vec<SystemJdk>> = OnceLock::new();
+
+/// The system JDKs, discovered on first use, which `init_cloud` makes happen at startup.
+///
+/// Scanning may run `java -version`, so it is done once and JDKs installed later need a restart.
+pub fn system_jdks() -> &'static [SystemJdk] {
+    SYSTEM_JDKS.get_or_init(discover)
+}
+
+/// Every JDK under `JAVA_HOME` and the configured search paths, each listed once.
+///
+/// A search path is either a JDK itself or a directory of JDKs like `/usr/lib/jvm`.
+fn discover() -> Vec<SystemJdk> {
+    let mut candidates = Vec::new();
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        candidates.push(PathBuf::from(java_home));
+    }
+    for path in &config::get().java.search_paths {
+        let path = Path::new(path);
+        if path.join("bin/java").is_file() {
+            candidates.push(path.to_path_buf());
+        } else if let Ok(entries) = read_dir(path) {
+            let mut homes = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>();
+            homes.sort();
+            candidates.extend(homes);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter_map(|home| canonicalize(home).ok())
+        .filter(|home| seen.insert(home.clone()))
+        .filter_map(|home| SystemJdk::probe(&home))
+        .collect()
+}
@@ -0,0 +1,100 @@
+/// Aikar's G1 tuning, recommended by PaperMC for Paper and its forks.
+pub fn aikar_flags() -> Vec<String> {
+    to_args(&[
+        "-XX:+UseG1GC",
+        "-XX:+ParallelRefProcEnabled",
+        "-XX:MaxGCPauseMillis=200",
+        "-XX:+UnlockExperimentalVMOptions",
+        "-XX:+DisableExplicitGC",
+        "-XX:+AlwaysPreTouch",
+        "-XX:G1NewSizePercent=30",
+        "-XX:G1MaxNewSizePercent=40",
+        "-XX:G1HeapRegionSize=8M",
+        "-XX:G1ReservePercent=20",
+        "-XX:G1HeapWastePercent=5",
+        "-XX:G1MixedGCCountTarget=4",
+        "-XX:InitiatingHeapOccupancyPercent=15",
+        "-XX:G1MixedGCLiveThresholdPercent=90",
+        "-XX:G1RSetUpdatingPauseTimePercent=5",
+        "-XX:SurvivorRatio=32",
+        "-XX:+PerfDisableSharedMem",
+        "-XX:MaxTenuringThreshold=1",
+        "-Dusing.aikars.flags=https://mcflags.emc.gs",
+        "-Daikars.new.flags=true",
+    ])
+}
+
+/// The flags the Velocity documentation recommends for proxies.
+pub fn velocity_flags() -> Vec<String> {
+    to_args(&[
+        "-XX:+UseG1GC",
+        "-XX:G1HeapRegionSize=4M",
+        "-XX:+UnlockExperimentalVMOptions",
+        "-XX:+ParallelRefProcEnabled",
+        "-XX:+AlwaysPreTouch",
+        "-XX:MaxInlineLevel=15",
+    ])
+}
+
+/// Minestom servers keep little long-lived state, ZGC keeps their pauses short. It is always
+/// generational on the Java 25 Yggdrasil requires, so `-XX:+ZGenerational` is not needed.
+pub fn minestom_flags() -> Vec<String> {
+    to_args(&[
+        "-XX:+UseZGC",
+        "-XX:+AlwaysPreTouch",
+        "-XX:+DisableExplicitGC",
+    ])
+}
+
+pub fn heap_flags(memory_mb: u32) -> Vec<String> {
+    vec![format!("-Xms{}M", memory_mb), format!("-Xmx{}M", memory_mb)]
+}
+
+fn to_args(flags: &[&str]) -> Vec<String> {
+    flags.iter().map(|flag| flag.to_string()).collect()
+}
+
+/// What an argument sets, so a later one for the same option replaces it.
+///
+/// `-XX:+Foo`, `-XX:-Foo` and `-XX:Foo=1` all set `Foo`, `-Dkey=value` sets `key`,
+/// `-Xmx2G` sets `-Xmx`, and every `-XX:+Use*GC` selects the collector.
+fn option_key(arg: &str) -> String {
+    if let Some(option) = arg.strip_prefix("-XX:") {
+        let name = option.trim_start_matches(['+', '-']);
+        let name = name.split('=').next().unwrap_or(name);
+        if name.starts_with("Use") && name.ends_with("GC") {
+            return "gc".to_string();
+        }
+        return format!("-XX:{}", name);
+    }
+    if let Some(property) = arg.strip_prefix("-D") {
+        return format!("-D{}", property.split('=').next().unwrap_or(property));
+    }
+    for sized in ["-Xms", "-Xmx", "-Xss", "-Xmn"] {
+        if arg.starts_with(sized) {
+            return sized.to_string();
+        }
+    }
+    arg.to_string()
+}
+
+/// Whether an argument only tunes G1, such as Aikar's `-XX:G1NewSizePercent=30`.
+fn is_g1_tuning(arg: &str) -> bool {
+    let key = option_key(arg);
+    key.starts_with("-XX:G1") || key == "-XX:InitiatingHeapOccupancyPercent"
+}
+
+/// Appends `overrides` to `defaults`, dropping any default that sets an option overridden later.
+/// Selecting another collector than G1 also drops the G1 tuning of the defaults.
+pub fn merge(defaults: Vec<String>, overrides: &[String]) -> Vec<String> {
+    let overridden = overrides.iter().map(|arg| option_key(arg)).collect::<Vec<_>>();
+    let leaves_g1 = overrides
+        .iter()
+        .any(|arg| option_key(arg) == "gc" && arg != "-XX:+UseG1GC");
+    defaults
+        .into_iter()
+        .filter(|arg| !overridden.contains(&option_key(arg)))
+        .filter(|arg| !(leaves_g1 && is_g1_tuning(arg)))
+        .chain(overrides.iter().cloned())
+        .collect()
+}
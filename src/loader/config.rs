@@ -1,9 +1,107 @@
+use crate::java::JavaVersion;
 use crate::minecraft_version::MinecraftVersion;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LoaderConfig {
-    Paper { version: MinecraftVersion },
-    Yggdrasil { version: MinecraftVersion },
+    Paper {
+        version: MinecraftVersion,
+        #[serde(default)]
+        build: Option<u32>,
+        #[serde(default)]
+        channel: PaperChannel,
+    },
+    Purpur {
+        version: MinecraftVersion,
+        #[serde(default)]
+        build: Option<String>,
+    },
+    Vanilla { version: MinecraftVersion },
+    Fabric {
+        version: MinecraftVersion,
+        loader_version: String,
+        installer_version: String,
+    },
+    Forge {
+        version: MinecraftVersion,
+        loader_version: String,
+    },
+    NeoForge {
+        version: MinecraftVersion,
+        loader_version: String,
+    },
+    Quilt {
+        version: MinecraftVersion,
+        loader_version: String,
+    },
+    Velocity {
+        version: String,
+        #[serde(default)]
+        build: Option<u32>,
+        #[serde(default)]
+        channel: PaperChannel,
+    },
+    Custom {
+        name: String,
+        /// Either an `http(s)://` URL or a path on the daemon host.
+        source: String,
+        java_version: JavaVersion,
+        /// Expected SHA-256 of the jar.
+        #[serde(default)]
+        checksum: Option<String>,
+        #[serde(default)]
+        version: Option<MinecraftVersion>,
+        #[serde(default)]
+        proxy: bool,
+    },
+    Yggdrasil {
+        version: MinecraftVersion,
+        #[serde(default)]
+        artifact_base_url: Option<String>,
+        /// Tag of the GitHub release to run, the newest matching one when unset.
+        #[serde(default)]
+        release: Option<String>,
+    },
 }
 
+impl LoaderConfig {
+    /// The same loader following its newest build, which upgrades look for.
+    pub fn unpinned(&self) -> Self {
+        let mut config = self.clone();
+        match &mut config {
+            LoaderConfig::Paper { build, .. } | LoaderConfig::Velocity { build, .. } => *build = None,
+            LoaderConfig::Purpur { build, .. } => *build = None,
+            LoaderConfig::Yggdrasil { release, .. } => *release = None,
+            _ => {}
+        }
+        config
+    }
+
+    /// The same loader pinned to `build`, which then gets its own cached artifact.
+    ///
+    /// `None` for loaders that cannot pin builds, or whose builds are fixed by their version.
+    pub fn pinned(&self, pinned: &str) -> Option<Self> {
+        let mut config = self.clone();
+        match &mut config {
+            LoaderConfig::Paper { build, .. } | LoaderConfig::Velocity { build, .. } => {
+                *build = Some(pinned.parse().ok()?)
+            }
+            LoaderConfig::Purpur { build, .. } => *build = Some(pinned.to_string()),
+            LoaderConfig::Yggdrasil {
+                artifact_base_url: None,
+                release,
+                ..
+            } => *release = Some(pinned.to_string()),
+            _ => return None,
+        }
+        Some(config)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperChannel {
+    #[default]
+    Stable,
+    Experimental,
+}
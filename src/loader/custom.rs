@@ -0,0 +1,92 @@
+use crate::errors::CloudError;
+use crate::file_downloader::{Checksum, copy_file, download_file, short_hash};
+use crate::java::{JavaRequirement, JavaVersion};
+use crate::loader::{LoaderBackend, ResolvedArtifact};
+use crate::minecraft_version::MinecraftVersion;
+use async_trait::async_trait;
+use std::fs::create_dir_all;
+use std::path::Path;
+
+/// An in-house jar, fetched from an arbitrary URL or copied from a local path.
+pub struct CustomLoader {
+    pub name: String,
+    pub source: String,
+    pub java_version: JavaVersion,
+    pub checksum: Option<String>,
+    pub version: Option<MinecraftVersion>,
+    pub proxy: bool,
+}
+
+impl CustomLoader {
+    fn is_remote(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+
+    /// The name ends up in a cache path, so it has to stay a single plain segment.
+    fn has_valid_name(&self) -> bool {
+        !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !self.name.starts_with('.')
+    }
+}
+
+#[async_trait]
+impl LoaderBackend for CustomLoader {
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        self.version.clone()
+    }
+
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::exactly(self.java_version)
+    }
+
+    /// Keyed by where the jar comes from too, so changing the source or checksum fetches it again.
+    fn artifact_id(&self) -> String {
+        let origin = format!("{}\n{}", self.source, self.checksum.as_deref().unwrap_or_default());
+        format!("{}-{}", self.name, short_hash(&origin))
+    }
+
+    fn is_proxy(&self) -> bool {
+        self.proxy
+    }
+
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        Ok(ResolvedArtifact {
+            url: self.source.clone(),
+            build: None,
+            checksum: self.checksum.clone().map(Checksum::Sha256),
+        })
+    }
+
+    /// There is no upstream to ask, only check that the jar can be cached and found.
+    async fn validate(&self) -> Result<(), CloudError> {
+        if !self.has_valid_name() {
+            return Err(CloudError::InvalidArtifactName);
+        }
+        if !self.is_remote() && !Path::new(&self.source).is_file() {
+            return Err(CloudError::NoArtifactForVersion);
+        }
+        Ok(())
+    }
+
+    async fn install(&self) -> Result<(), CloudError> {
+        if !self.has_valid_name() {
+            return Err(CloudError::InvalidArtifactName);
+        }
+        create_dir_all(format!("versions/{}", self.name())).map_err(|_| CloudError::FileError)?;
+
+        let artifact = self.resolve_artifact().await?;
+        if self.is_remote() {
+            download_file(&artifact.url, &self.jar_path(), artifact.checksum.as_ref()).await
+        } else {
+            copy_file(&artifact.url, &self.jar_path(), artifact.checksum.as_ref()).await
+        }
+    }
+}
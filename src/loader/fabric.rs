@@ -0,0 +1,115 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::fetch_json;
+use crate::java::JavaRequirement;
+use crate::loader::{LoaderBackend, LoaderBuild, ResolvedArtifact, ensure_listed};
+use crate::minecraft_version::MinecraftVersion;
+use async_trait::async_trait;
+
+pub struct FabricLoader {
+    pub version: MinecraftVersion,
+    pub loader_version: String,
+    pub installer_version: String,
+}
+
+fn meta_url() -> String {
+    format!("{}/v2/versions", config::get().upstreams.fabric_meta)
+}
+
+/// Lists one of Fabric's meta endpoints: `game`, `loader` or `installer`.
+async fn fabric_versions(component: &str) -> Result<Vec<String>, CloudError> {
+    let resp = fetch_json(&format!("{}/{}", meta_url(), component)).await?;
+    Ok(resp
+        .as_array()
+        .ok_or(CloudError::JSONError)?
+        .iter()
+        .filter_map(|v| v["version"].as_str().map(str::to_string))
+        .collect())
+}
+
+/// The newest installer Fabric marks stable, used when nothing pins one.
+pub async fn latest_stable_installer() -> Result<String, CloudError> {
+    let resp = fetch_json(&format!("{}/installer", meta_url())).await?;
+    resp.as_array()
+        .ok_or(CloudError::JSONError)?
+        .iter()
+        .find(|v| v["stable"].as_bool().unwrap_or(false))
+        .and_then(|v| v["version"].as_str())
+        .map(str::to_string)
+        .ok_or(CloudError::NoStableBuild)
+}
+
+#[async_trait]
+impl LoaderBackend for FabricLoader {
+    fn name(&self) -> &'static str {
+        "fabric"
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        Some(self.version.clone())
+    }
+
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::for_minecraft(&self.version)
+    }
+
+    fn artifact_id(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.version.get(),
+            self.loader_version,
+            self.installer_version
+        )
+    }
+
+    fn content_dir(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["fabric"]
+    }
+
+    /// Fabric's meta API builds the server launcher on demand for a game/loader/installer triple.
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        Ok(ResolvedArtifact {
+            url: format!(
+                "{}/loader/{}/{}/{}/server/jar",
+                meta_url(),
+                self.version.get(),
+                self.loader_version,
+                self.installer_version
+            ),
+            build: Some(self.loader_version.clone()),
+            checksum: None,
+        })
+    }
+
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        fabric_versions("game").await
+    }
+
+    /// Loader versions usable with this game version, the installer is picked independently.
+    async fn list_builds(&self, version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+        let resp = fetch_json(&format!("{}/loader/{}", meta_url(), version)).await?;
+        let mut builds = resp
+            .as_array()
+            .ok_or(CloudError::JSONError)?
+            .iter()
+            .filter_map(|b| {
+                Some(LoaderBuild {
+                    build: b["loader"]["version"].as_str()?.to_string(),
+                    stable: b["loader"]["stable"].as_bool().unwrap_or(false),
+                })
+            })
+            .collect::<Vec<_>>();
+        builds.reverse();
+        Ok(builds)
+    }
+
+    async fn validate(&self) -> Result<(), CloudError> {
+        ensure_listed(&self.upstream_versions().await?, self.version.get())?;
+        ensure_listed(&fabric_versions("loader").await?, &self.loader_version)?;
+        ensure_listed(&fabric_versions("installer").await?, &self.installer_version)
+    }
+}
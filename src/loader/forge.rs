@@ -0,0 +1,300 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::{download_file, fetch_json};
+use crate::installs::ensure_java;
+use crate::java::{JavaRequirement, JavaRuntime};
+use crate::loader::{LoaderBackend, LoaderBuild, ResolvedArtifact, ensure_listed};
+use crate::minecraft_version::MinecraftVersion;
+use async_trait::async_trait;
+use std::fs::{canonicalize, create_dir_all, remove_file, symlink_metadata};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use tokio::process::Command;
+
+#[derive(Clone, Copy)]
+pub enum ForgeFlavor {
+    Forge,
+    NeoForge,
+}
+
+/// Forge-family servers are produced by running the upstream installer, which lays out
+/// `libraries/` and an args file instead of shipping a single runnable jar.
+pub struct ForgeLoader {
+    pub flavor: ForgeFlavor,
+    pub version: MinecraftVersion,
+    pub loader_version: String,
+}
+
+impl ForgeLoader {
+    fn install_dir(&self) -> String {
+        format!(
+            "versions/{}/{}-{}",
+            self.name(),
+            self.name(),
+            self.artifact_id()
+        )
+    }
+
+    /// Location of the generated `unix_args.txt`, relative to the install directory.
+    fn args_file(&self) -> String {
+        match self.flavor {
+            ForgeFlavor::Forge => format!(
+                "libraries/net/minecraftforge/forge/{}-{}/unix_args.txt",
+                self.version.get(),
+                self.loader_version
+            ),
+            ForgeFlavor::NeoForge => format!(
+                "libraries/net/neoforged/neoforge/{}/unix_args.txt",
+                self.loader_version
+            ),
+        }
+    }
+}
+
+/// Forge installers only lay out `unix_args.txt` from 1.17 on, older ones produce a single jar.
+/// Forge has no builds for snapshots.
+fn has_args_file(version: &MinecraftVersion) -> bool {
+    version.release().is_some_and(|release| release >= (1, 17, 0))
+}
+
+fn forge_versions_url() -> String {
+    format!(
+        "{}/net/minecraftforge/forge/maven-metadata.json",
+        config::get().upstreams.forge_files
+    )
+}
+
+fn neoforge_versions_url() -> String {
+    format!(
+        "{}/api/maven/versions/releases/net/neoforged/neoforge",
+        config::get().upstreams.neoforge_maven
+    )
+}
+
+/// NeoForge versions encode the game version they target: `21.1.77` is for `1.21.1`,
+/// `21.0.167` for `1.21`, and from `26.1` on the game version is used as is.
+fn neoforge_minecraft_version(neoforge: &str) -> Option<String> {
+    let numbers = neoforge.split('-').next()?;
+    let parts = numbers.split('.').collect::<Vec<_>>();
+    let major = parts.first()?.parse::<u32>().ok()?;
+    let minor = parts.get(1)?;
+    if major >= 26 {
+        return match parts.get(2) {
+            Some(patch) if parts.len() > 3 && *patch != "0" => {
+                Some(format!("{}.{}.{}", major, minor, patch))
+            }
+            _ => Some(format!("{}.{}", major, minor)),
+        };
+    }
+    if *minor == "0" {
+        Some(format!("1.{}", major))
+    } else {
+        Some(format!("1.{}.{}", major, minor))
+    }
+}
+
+async fn neoforge_versions() -> Result<Vec<String>, CloudError> {
+    let resp = fetch_json(&neoforge_versions_url()).await?;
+    Ok(resp["versions"]
+        .as_array()
+        .ok_or(CloudError::JSONError)?
+        .iter()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect())
+}
+
+#[async_trait]
+impl LoaderBackend for ForgeLoader {
+    fn name(&self) -> &'static str {
+        match self.flavor {
+            ForgeFlavor::Forge => "forge",
+            ForgeFlavor::NeoForge => "neoforge",
+        }
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        Some(self.version.clone())
+    }
+
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::for_minecraft(&self.version)
+    }
+
+    fn artifact_id(&self) -> String {
+        format!("{}-{}", self.version.get(), self.loader_version)
+    }
+
+    fn content_dir(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        match self.flavor {
+            ForgeFlavor::Forge => &["forge"],
+            ForgeFlavor::NeoForge => &["neoforge"],
+        }
+    }
+
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        let url = match self.flavor {
+            ForgeFlavor::Forge => {
+                let coordinate = format!("{}-{}", self.version.get(), self.loader_version);
+                format!(
+                    "{}/net/minecraftforge/forge/{}/forge-{}-installer.jar",
+                    config::get().upstreams.forge_maven,
+                    coordinate,
+                    coordinate
+                )
+            }
+            ForgeFlavor::NeoForge => format!(
+                "{}/releases/net/neoforged/neoforge/{}/neoforge-{}-installer.jar",
+                config::get().upstreams.neoforge_maven,
+                self.loader_version,
+                self.loader_version
+            ),
+        };
+
+        Ok(ResolvedArtifact {
+            url,
+            build: Some(self.loader_version.clone()),
+            checksum: None,
+        })
+    }
+
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        match self.flavor {
+            ForgeFlavor::Forge => {
+                let resp = fetch_json(&forge_versions_url()).await?;
+                Ok(resp
+                    .as_object()
+                    .ok_or(CloudError::JSONError)?
+                    .keys()
+                    .filter(|v| MinecraftVersion::parse(v).is_ok_and(|v| has_args_file(&v)))
+                    .cloned()
+                    .collect())
+            }
+            ForgeFlavor::NeoForge => {
+                let mut versions = neoforge_versions()
+                    .await?
+                    .iter()
+                    .filter_map(|v| neoforge_minecraft_version(v))
+                    .collect::<Vec<_>>();
+                versions.dedup();
+                Ok(versions)
+            }
+        }
+    }
+
+    async fn list_builds(&self, version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+        match self.flavor {
+            ForgeFlavor::Forge => {
+                let resp = fetch_json(&forge_versions_url()).await?;
+                let prefix = format!("{}-", version);
+                Ok(resp[version]
+                    .as_array()
+                    .ok_or(CloudError::UnknownVersion)?
+                    .iter()
+                    .filter_map(|v| {
+                        Some(LoaderBuild {
+                            build: v.as_str()?.strip_prefix(&prefix)?.to_string(),
+                            stable: true,
+                        })
+                    })
+                    .collect())
+            }
+            ForgeFlavor::NeoForge => Ok(neoforge_versions()
+                .await?
+                .into_iter()
+                .filter(|v| neoforge_minecraft_version(v).as_deref() == Some(version))
+                .map(|v| LoaderBuild {
+                    stable: !v.contains("-beta"),
+                    build: v,
+                })
+                .collect()),
+        }
+    }
+
+    async fn validate(&self) -> Result<(), CloudError> {
+        match self.flavor {
+            ForgeFlavor::Forge => {
+                if !has_args_file(&self.version) {
+                    return Err(CloudError::Unsupported);
+                }
+                let resp = fetch_json(&forge_versions_url()).await?;
+                let builds = resp[self.version.get()]
+                    .as_array()
+                    .ok_or(CloudError::UnknownVersion)?
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect::<Vec<_>>();
+                ensure_listed(&builds, &self.artifact_id())
+            }
+            ForgeFlavor::NeoForge => {
+                ensure_listed(&neoforge_versions().await?, &self.loader_version)?;
+                if neoforge_minecraft_version(&self.loader_version).as_deref()
+                    == Some(self.version.get())
+                {
+                    Ok(())
+                } else {
+                    Err(CloudError::UnknownVersion)
+                }
+            }
+        }
+    }
+
+    async fn install(&self) -> Result<(), CloudError> {
+        let install_dir = self.install_dir();
+        create_dir_all(&install_dir).map_err(|_| CloudError::FileError)?;
+
+        let artifact = self.resolve_artifact().await?;
+        let installer = format!("{}/installer.jar", install_dir);
+        download_file(&artifact.url, &installer, artifact.checksum.as_ref()).await?;
+
+        let runtime =
+            JavaRuntime::select(self.java_requirement(), self.java_version(), None, None);
+        ensure_java(&runtime).await?;
+        let java = canonicalize(runtime.java_path("."))
+            .map_err(|_| CloudError::FileError)?;
+
+        let status = Command::new(java)
+            .arg("-jar")
+            .arg("installer.jar")
+            .arg("--installServer")
+            .current_dir(&install_dir)
+            .status()
+            .await
+            .map_err(|_| CloudError::InstallerError)?;
+
+        let _ = remove_file(&installer);
+        if !status.success() || !self.is_installed() {
+            return Err(CloudError::InstallerError);
+        }
+
+        Ok(())
+    }
+
+    fn is_installed(&self) -> bool {
+        Path::new(&self.install_dir())
+            .join(self.args_file())
+            .exists()
+    }
+
+    fn installed_build(&self) -> Option<String> {
+        Some(self.loader_version.clone())
+    }
+
+    /// The args file references `libraries/` relatively, so the cached tree is linked in.
+    fn prepare_instance(&self, dir: &str) -> Result<(), CloudError> {
+        let link = Path::new(dir).join("libraries");
+        if symlink_metadata(&link).is_ok() {
+            remove_file(&link).map_err(|_| CloudError::FileError)?;
+        }
+        let libraries = canonicalize(format!("{}/libraries", self.install_dir()))
+            .map_err(|_| CloudError::FileError)?;
+        symlink(libraries, link).map_err(|_| CloudError::FileError)
+    }
+
+    fn launch_args(&self, _root: &str) -> Vec<String> {
+        vec![format!("@{}", self.args_file()), "nogui".to_string()]
+    }
+}
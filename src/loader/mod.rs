@@ -1,60 +1,322 @@
 pub mod config;
+pub mod custom;
+pub mod fabric;
+pub mod forge;
 pub mod paper;
+pub mod purpur;
+pub mod quilt;
+pub mod vanilla;
+pub mod velocity;
 pub mod yggdrasil;
 
 use crate::errors::CloudError;
-use crate::file_downloader::download_file;
-use crate::loader::config::LoaderConfig;
+use crate::file_downloader::{Checksum, download_file};
+use crate::instance::Instance;
+use crate::java::{JavaRequirement, JavaVersion};
+use crate::loader::config::{LoaderConfig, PaperChannel};
+use crate::loader::custom::CustomLoader;
+use crate::loader::fabric::FabricLoader;
+use crate::loader::forge::{ForgeFlavor, ForgeLoader};
 use crate::loader::paper::PaperLoader;
+use crate::loader::purpur::PurpurLoader;
+use crate::loader::quilt::QuiltLoader;
+use crate::loader::vanilla::VanillaLoader;
+use crate::loader::velocity::VelocityLoader;
 use crate::loader::yggdrasil::YggdrasilLoader;
 use crate::minecraft_version::MinecraftVersion;
-use crate::screen_manager::JavaVersion;
 use async_trait::async_trait;
-use std::fs::create_dir_all;
+use serde::Serialize;
+use std::fs::{create_dir_all, read_to_string, write};
 use std::path::Path;
 use std::sync::Arc;
 
+/// Loaders with an upstream catalog, custom jars have none to list.
+pub const LOADER_NAMES: [&str; 9] = [
+    "paper",
+    "purpur",
+    "vanilla",
+    "fabric",
+    "forge",
+    "neoforge",
+    "quilt",
+    "velocity",
+    "yggdrasil",
+];
+
+#[derive(Serialize)]
+pub struct LoaderBuild {
+    pub build: String,
+    pub stable: bool,
+}
+
+pub struct ResolvedArtifact {
+    pub url: String,
+    pub build: Option<String>,
+    pub checksum: Option<Checksum>,
+}
+
 #[async_trait]
 pub trait LoaderBackend: Send + Sync {
     fn name(&self) -> &'static str;
-    fn version(&self) -> MinecraftVersion;
-    fn java_version(&self) -> JavaVersion;
+    /// The game version served, `None` for proxies which sit in front of any version.
+    fn minecraft_version(&self) -> Option<MinecraftVersion>;
+    fn java_requirement(&self) -> JavaRequirement;
+
+    /// The JDK installed and run with, see `JavaRequirement::preferred`.
+    fn java_version(&self) -> JavaVersion {
+        self.java_requirement().preferred()
+    }
+
+    /// Identifies the cached artifact, loaders that pin builds should include them here.
+    fn artifact_id(&self) -> String;
+
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError>;
 
-    async fn resolve_download_url(&self) -> Result<String, CloudError>;
+    /// Versions the upstream publishes builds for, when it exposes such a list.
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        Err(CloudError::Unsupported)
+    }
+
+    /// Builds published for one of the `upstream_versions`, oldest first.
+    async fn list_builds(&self, _version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+        Err(CloudError::Unsupported)
+    }
+
+    /// The build a fresh install would resolve to now, compared with `installed_build` on upgrades.
+    async fn latest_build(&self) -> Result<Option<String>, CloudError> {
+        Ok(self.resolve_artifact().await?.build)
+    }
+
+    /// Run at registration so an unknown version is refused before the server is created.
+    async fn validate(&self) -> Result<(), CloudError> {
+        match self.minecraft_version() {
+            Some(version) => ensure_listed(&self.upstream_versions().await?, version.get()),
+            None => Ok(()),
+        }
+    }
+
+    fn is_proxy(&self) -> bool {
+        self.minecraft_version().is_none()
+    }
+
+    fn artifact_path(&self, extension: &str) -> String {
+        format!(
+            "versions/{}/{}-{}.{}",
+            self.name(),
+            self.name(),
+            self.artifact_id(),
+            extension
+        )
+    }
+
+    fn jar_path(&self) -> String {
+        self.artifact_path("jar")
+    }
+
+    /// Folder inside the instance directory holding plugins or mods, if the loader has one.
+    fn content_dir(&self) -> Option<&'static str> {
+        None
+    }
 
     async fn install(&self) -> Result<(), CloudError> {
         let local = format!("versions/{}", self.name());
-        let jar_name = format!("{}-{}.jar", self.name(), self.version().get());
         if !Path::new(&local).exists() {
             create_dir_all(&local).map_err(|_| CloudError::FileError)?;
         }
 
-        let url = self.resolve_download_url().await?;
+        let artifact = self.resolve_artifact().await?;
 
-        download_file(&url, &format!("{}/{}", local, jar_name)).await?;
+        download_file(&artifact.url, &self.jar_path(), artifact.checksum.as_ref()).await?;
+
+        if let Some(build) = artifact.build {
+            write(self.artifact_path("build"), build).map_err(|_| CloudError::FileError)?;
+        }
 
         Ok(())
     }
 
     fn is_installed(&self) -> bool {
-        let exec = format!(
-            "versions/{}/{}-{}.jar",
-            self.name(),
-            self.name(),
-            self.version().get()
-        );
-        Path::new(&exec).exists()
+        Path::new(&self.jar_path()).exists()
+    }
+
+    /// The upstream build the cached jar was resolved to, if the loader publishes builds.
+    fn installed_build(&self) -> Option<String> {
+        read_to_string(self.artifact_path("build")).ok()
+    }
+
+    /// Loader names Modrinth tags compatible plugins and mods with, most specific first.
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &[]
     }
+
+    /// The Hangar platform plugins for this loader are published for.
+    fn hangar_platform(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Generates or patches the loader's own config files, on creation and before each start.
+    ///
+    /// `proxied` is set for backends reached through a proxy, see `proxy::is_proxied`.
+    fn write_config_files(&self, _instance: &Instance, _proxied: bool) -> Result<(), CloudError> {
+        Ok(())
+    }
+
+    /// Hook run in the instance directory before each start, once the loader is installed.
+    fn prepare_instance(&self, _dir: &str) -> Result<(), CloudError> {
+        Ok(())
+    }
+
+    /// JVM flags put before the instance's own, which may override them.
+    fn default_jvm_args(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Arguments passed to `java`, with `root` being the daemon directory seen from the instance.
+    fn launch_args(&self, root: &str) -> Vec<String> {
+        vec![
+            "-jar".to_string(),
+            format!("{}/{}", root, self.jar_path()),
+            "nogui".to_string(),
+        ]
+    }
+}
+
+pub fn ensure_listed(versions: &[String], wanted: &str) -> Result<(), CloudError> {
+    if versions.iter().any(|v| v == wanted) {
+        Ok(())
+    } else {
+        Err(CloudError::UnknownVersion)
+    }
+}
+
+/// A loader usable for catalog queries only, its version fields are placeholders.
+pub fn catalog_loader(name: &str) -> Option<Arc<dyn LoaderBackend>> {
+    let version = MinecraftVersion::parse("1.21.10").ok()?;
+    let config = match name {
+        "paper" => LoaderConfig::Paper {
+            version,
+            build: None,
+            channel: PaperChannel::Stable,
+        },
+        "purpur" => LoaderConfig::Purpur {
+            version,
+            build: None,
+        },
+        "vanilla" => LoaderConfig::Vanilla { version },
+        "fabric" => LoaderConfig::Fabric {
+            version,
+            loader_version: String::new(),
+            installer_version: String::new(),
+        },
+        "forge" => LoaderConfig::Forge {
+            version,
+            loader_version: String::new(),
+        },
+        "neoforge" => LoaderConfig::NeoForge {
+            version,
+            loader_version: String::new(),
+        },
+        "quilt" => LoaderConfig::Quilt {
+            version,
+            loader_version: String::new(),
+        },
+        "velocity" => LoaderConfig::Velocity {
+            version: String::new(),
+            build: None,
+            channel: PaperChannel::Stable,
+        },
+        "yggdrasil" => LoaderConfig::Yggdrasil {
+            version,
+            artifact_base_url: None,
+            release: None,
+        },
+        _ => return None,
+    };
+    Some(build_loader(&config))
 }
 
 pub fn build_loader(config: &LoaderConfig) -> Arc<dyn LoaderBackend> {
     match config {
-        LoaderConfig::Paper { version } => Arc::new(PaperLoader {
+        LoaderConfig::Paper {
+            version,
+            build,
+            channel,
+        } => Arc::new(PaperLoader {
             version: version.clone(),
+            build: *build,
+            channel: *channel,
         }),
-        LoaderConfig::Yggdrasil { version } => Arc::new(YggdrasilLoader {
+        LoaderConfig::Purpur { version, build } => Arc::new(PurpurLoader {
             version: version.clone(),
+            build: build.clone(),
+        }),
+        LoaderConfig::Vanilla { version } => Arc::new(VanillaLoader {
+            version: version.clone(),
+        }),
+        LoaderConfig::Fabric {
+            version,
+            loader_version,
+            installer_version,
+        } => Arc::new(FabricLoader {
+            version: version.clone(),
+            loader_version: loader_version.clone(),
+            installer_version: installer_version.clone(),
+        }),
+        LoaderConfig::Forge {
+            version,
+            loader_version,
+        } => Arc::new(ForgeLoader {
+            flavor: ForgeFlavor::Forge,
+            version: version.clone(),
+            loader_version: loader_version.clone(),
+        }),
+        LoaderConfig::NeoForge {
+            version,
+            loader_version,
+        } => Arc::new(ForgeLoader {
+            flavor: ForgeFlavor::NeoForge,
+            version: version.clone(),
+            loader_version: loader_version.clone(),
+        }),
+        LoaderConfig::Quilt {
+            version,
+            loader_version,
+        } => Arc::new(QuiltLoader {
+            version: version.clone(),
+            loader_version: loader_version.clone(),
+        }),
+        LoaderConfig::Velocity {
+            version,
+            build,
+            channel,
+        } => Arc::new(VelocityLoader {
+            version: version.clone(),
+            build: *build,
+            channel: *channel,
+        }),
+        LoaderConfig::Custom {
+            name,
+            source,
+            java_version,
+            checksum,
+            version,
+            proxy,
+        } => Arc::new(CustomLoader {
+            name: name.clone(),
+            source: source.clone(),
+            java_version: *java_version,
+            checksum: checksum.clone(),
+            version: version.clone(),
+            proxy: *proxy,
+        }),
+        LoaderConfig::Yggdrasil {
+            version,
+            artifact_base_url,
+            release,
+        } => Arc::new(YggdrasilLoader {
+            version: version.clone(),
+            artifact_base_url: artifact_base_url.clone(),
+            release: release.clone(),
         }),
     }
 }
-
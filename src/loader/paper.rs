@@ -1,11 +1,165 @@
 use async_trait::async_trait;
+use crate::config;
+use crate::config::ForwardingMode;
 use crate::errors::CloudError;
-use crate::loader::LoaderBackend;
+use crate::file_downloader::{Checksum, fetch_json};
+use crate::instance::Instance;
+use crate::java::JavaRequirement;
+use crate::jvm;
+use crate::loader::{LoaderBackend, LoaderBuild, ResolvedArtifact};
+use crate::loader::config::PaperChannel;
 use crate::minecraft_version::MinecraftVersion;
-use crate::screen_manager::JavaVersion;
+use crate::server_files::{forwarding_secret, patch_properties, patch_yaml};
 
 pub struct PaperLoader {
 	pub version: MinecraftVersion,
+	pub build: Option<u32>,
+	pub channel: PaperChannel,
+}
+
+/// Resolves a build of any PaperMC project (Paper, Velocity...) through the v2 downloads API.
+pub async fn resolve_papermc_build(
+	project: &str,
+	version: &str,
+	pinned: Option<u32>,
+	channel: PaperChannel,
+) -> Result<ResolvedArtifact, CloudError> {
+	let url = format!(
+		"{}/v2/projects/{}/versions/{}/builds",
+		config::get().upstreams.papermc, project, version
+	);
+
+	let resp = fetch_json(&url).await?;
+	let builds = resp["builds"].as_array().ok_or(CloudError::JSONError)?;
+	let accepts = |build: &serde_json::Value| {
+		channel == PaperChannel::Experimental || build["channel"].as_str() == Some("default")
+	};
+
+	let build = match pinned {
+		Some(pinned) => {
+			let build = builds
+				.iter()
+				.find(|b| b["build"].as_u64() == Some(pinned as u64))
+				.ok_or(CloudError::NoArtifactForVersion)?;
+			if !accepts(build) {
+				return Err(CloudError::NoStableBuild);
+			}
+			build
+		}
+		None => builds
+			.iter()
+			.rev()
+			.find(|b| accepts(b))
+			.ok_or(CloudError::NoStableBuild)?,
+	};
+
+	let build_number = build["build"].as_i64().ok_or(CloudError::JSONError)?;
+	let application = &build["downloads"]["application"];
+	let jar_name = application["name"].as_str().ok_or(CloudError::JSONError)?;
+	let sha256 = application["sha256"].as_str().ok_or(CloudError::JSONError)?;
+
+	Ok(ResolvedArtifact {
+		url: format!(
+			"{}/v2/projects/{}/versions/{}/builds/{}/downloads/{}",
+			config::get().upstreams.papermc, project, version, build_number, jar_name
+		),
+		build: Some(build_number.to_string()),
+		checksum: Some(Checksum::Sha256(sha256.to_string())),
+	})
+}
+
+pub async fn papermc_versions(project: &str) -> Result<Vec<String>, CloudError> {
+	let resp = fetch_json(&format!("{}/v2/projects/{}", config::get().upstreams.papermc, project)).await?;
+	Ok(resp["versions"]
+		.as_array()
+		.ok_or(CloudError::JSONError)?
+		.iter()
+		.filter_map(|v| v.as_str().map(str::to_string))
+		.collect())
+}
+
+pub async fn papermc_builds(project: &str, version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+	let url = format!(
+		"{}/v2/projects/{}/versions/{}/builds",
+		config::get().upstreams.papermc, project, version
+	);
+	let resp = fetch_json(&url).await?;
+	Ok(resp["builds"]
+		.as_array()
+		.ok_or(CloudError::JSONError)?
+		.iter()
+		.filter_map(|b| {
+			Some(LoaderBuild {
+				build: b["build"].as_u64()?.to_string(),
+				stable: b["channel"].as_str() == Some("default"),
+			})
+		})
+		.collect())
+}
+
+/// Cache key shared by PaperMC projects, a pinned build or the experimental channel get their own jar.
+pub fn papermc_artifact_id(version: &str, pinned: Option<u32>, channel: PaperChannel) -> String {
+	match (pinned, channel) {
+		(Some(build), _) => format!("{}-{}", version, build),
+		(None, PaperChannel::Stable) => version.to_string(),
+		(None, PaperChannel::Experimental) => format!("{}-experimental", version),
+	}
+}
+
+/// Sets the port and player limit of a Paper-family backend, and when it sits behind a proxy,
+/// the forwarding the daemon uses. Standalone servers keep their own online mode and throttle.
+///
+/// Backends of a managed proxy only listen on loopback, where it reaches them, so players
+/// cannot bypass the proxy with a forged profile.
+pub fn write_paper_configs(version: &MinecraftVersion, instance: &Instance, proxied: bool) -> Result<(), CloudError> {
+	let dir = instance.directory();
+	let forwarding = config::get().proxy.forwarding;
+	let proxied = proxied && forwarding != ForwardingMode::None;
+
+	let mut properties = vec![
+		("server-port", instance.port.to_string()),
+		("max-players", instance.max_player.to_string()),
+	];
+	if proxied {
+		// Players are authenticated by the proxy, which forwards their profile.
+		properties.push(("online-mode", "false".to_string()));
+		if !instance.behind_proxy {
+			properties.push(("server-ip", "127.0.0.1".to_string()));
+		}
+	}
+	patch_properties(&format!("{}/server.properties", dir), &properties)?;
+	if !proxied {
+		return Ok(());
+	}
+
+	patch_yaml(
+		&format!("{}/spigot.yml", dir),
+		&[(&["settings", "bungeecord"], (forwarding == ForwardingMode::Legacy).to_string())],
+	)?;
+	// Every player comes from the proxy address, throttling it would lock them out.
+	patch_yaml(
+		&format!("{}/bukkit.yml", dir),
+		&[(&["settings", "connection-throttle"], "-1".to_string())],
+	)?;
+
+	// Paper moved its settings to config/paper-global.yml in 1.19.
+	let (path, [table, section]) = match version.release() {
+		Some(release) if release < (1, 19, 0) => (format!("{}/paper.yml", dir), ["settings", "velocity-support"]),
+		_ => (format!("{}/config/paper-global.yml", dir), ["proxies", "velocity"]),
+	};
+	let enabled = [table, section, "enabled"];
+	let online_mode = [table, section, "online-mode"];
+	let secret = [table, section, "secret"];
+
+	let modern = forwarding == ForwardingMode::Modern;
+	let mut values: Vec<(&[&str], String)> = vec![
+		(&enabled, modern.to_string()),
+		(&online_mode, "true".to_string()),
+	];
+	if modern {
+		values.push((&secret, format!("'{}'", forwarding_secret()?)));
+	}
+	patch_yaml(&path, &values)
 }
 
 #[async_trait]
@@ -14,36 +168,47 @@ impl LoaderBackend for PaperLoader {
 		"paper"
 	}
 
-	fn version(&self) -> MinecraftVersion {
-		self.version.clone()
+	fn minecraft_version(&self) -> Option<MinecraftVersion> {
+		Some(self.version.clone())
 	}
 
-	fn java_version(&self) -> JavaVersion {
-		match self.version {
-			MinecraftVersion::V1_21_10 |
-			MinecraftVersion::V1_21_11
-			=> JavaVersion::J21
-		}
+	fn java_requirement(&self) -> JavaRequirement {
+		JavaRequirement::for_minecraft(&self.version)
 	}
 
-	async fn resolve_download_url(&self) -> Result<String, CloudError> {
-		let url = format!(
-			"https://api.papermc.io/v2/projects/paper/versions/{}/builds",
-			self.version.get()
-		);
+	fn artifact_id(&self) -> String {
+		papermc_artifact_id(self.version.get(), self.build, self.channel)
+	}
 
-		let resp: serde_json::Value = reqwest::get(url).await.map_err(|_| CloudError::HTTPError)?.json().await.map_err(|_| CloudError::JSONError)?;
+	fn content_dir(&self) -> Option<&'static str> {
+		Some("plugins")
+	}
 
-		let build = resp["builds"]
-			.as_array()
-			.and_then(|b| b.last())
-			.ok_or(CloudError::NoStableBuild)?;
+	fn modrinth_loaders(&self) -> &'static [&'static str] {
+		&["paper", "spigot", "bukkit"]
+	}
 
-		let build_number = build["build"].as_i64().ok_or(CloudError::JSONError)?;
+	fn hangar_platform(&self) -> Option<&'static str> {
+		Some("PAPER")
+	}
 
-		Ok(format!(
-			"https://api.papermc.io/v2/projects/paper/versions/{}/builds/{}/downloads/paper-{}-{}.jar",
-			self.version.get(), build_number, self.version.get(), build_number
-		))
+	fn default_jvm_args(&self) -> Vec<String> {
+		jvm::aikar_flags()
 	}
-}
\ No newline at end of file
+
+	fn write_config_files(&self, instance: &Instance, proxied: bool) -> Result<(), CloudError> {
+		write_paper_configs(&self.version, instance, proxied)
+	}
+
+	async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+		resolve_papermc_build("paper", self.version.get(), self.build, self.channel).await
+	}
+
+	async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+		papermc_versions("paper").await
+	}
+
+	async fn list_builds(&self, version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+		papermc_builds("paper", version).await
+	}
+}
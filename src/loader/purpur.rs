@@ -0,0 +1,114 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::fetch_json;
+use crate::instance::Instance;
+use crate::java::JavaRequirement;
+use crate::jvm;
+use crate::loader::paper::write_paper_configs;
+use crate::loader::{LoaderBackend, LoaderBuild, ResolvedArtifact};
+use crate::minecraft_version::MinecraftVersion;
+use async_trait::async_trait;
+
+pub struct PurpurLoader {
+    pub version: MinecraftVersion,
+    pub build: Option<String>,
+}
+
+#[async_trait]
+impl LoaderBackend for PurpurLoader {
+    fn name(&self) -> &'static str {
+        "purpur"
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        Some(self.version.clone())
+    }
+
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::for_minecraft(&self.version)
+    }
+
+    /// A pinned build gets its own jar.
+    fn artifact_id(&self) -> String {
+        match &self.build {
+            Some(build) => format!("{}-{}", self.version.get(), build),
+            None => self.version.get().to_string(),
+        }
+    }
+
+    fn content_dir(&self) -> Option<&'static str> {
+        Some("plugins")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["purpur", "paper", "spigot", "bukkit"]
+    }
+
+    fn hangar_platform(&self) -> Option<&'static str> {
+        Some("PAPER")
+    }
+
+    fn default_jvm_args(&self) -> Vec<String> {
+        jvm::aikar_flags()
+    }
+
+    fn write_config_files(&self, instance: &Instance, proxied: bool) -> Result<(), CloudError> {
+        write_paper_configs(&self.version, instance, proxied)
+    }
+
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        let url = format!("{}/v2/purpur/{}", config::get().upstreams.purpur, self.version.get());
+
+        let resp = fetch_json(&url).await?;
+
+        let build = match &self.build {
+            Some(pinned) => resp["builds"]["all"]
+                .as_array()
+                .ok_or(CloudError::JSONError)?
+                .iter()
+                .filter_map(|b| b.as_str())
+                .find(|b| b == pinned)
+                .ok_or(CloudError::NoArtifactForVersion)?,
+            None => resp["builds"]["latest"]
+                .as_str()
+                .ok_or(CloudError::NoStableBuild)?,
+        };
+
+        Ok(ResolvedArtifact {
+            url: format!(
+                "{}/v2/purpur/{}/{}/download",
+                config::get().upstreams.purpur,
+                self.version.get(),
+                build
+            ),
+            build: Some(build.to_string()),
+            checksum: None,
+        })
+    }
+
+    async fn list_builds(&self, version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+        let url = format!("{}/v2/purpur/{}", config::get().upstreams.purpur, version);
+        let resp = fetch_json(&url).await?;
+        Ok(resp["builds"]["all"]
+            .as_array()
+            .ok_or(CloudError::JSONError)?
+            .iter()
+            .filter_map(|b| {
+                Some(LoaderBuild {
+                    build: b.as_str()?.to_string(),
+                    stable: true,
+                })
+            })
+            .collect())
+    }
+
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        let resp = fetch_json(&format!("{}/v2/purpur", config::get().upstreams.purpur)).await?;
+        Ok(resp["versions"]
+            .as_array()
+            .ok_or(CloudError::JSONError)?
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect())
+    }
+}
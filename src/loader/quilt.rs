@@ -0,0 +1,182 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::{download_file, fetch_json};
+use crate::installs::ensure_java;
+use crate::java::{JavaRequirement, JavaRuntime};
+use crate::loader::{LoaderBackend, LoaderBuild, ResolvedArtifact, ensure_listed};
+use crate::minecraft_version::MinecraftVersion;
+use async_trait::async_trait;
+use std::fs::{canonicalize, create_dir_all, remove_file, symlink_metadata};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use tokio::process::Command;
+
+const LAUNCH_JAR: &str = "quilt-server-launch.jar";
+
+/// Quilt has no prebuilt server launcher, its installer lays out the launcher, the vanilla
+/// `server.jar` and `libraries/` in the cache.
+pub struct QuiltLoader {
+    pub version: MinecraftVersion,
+    pub loader_version: String,
+}
+
+impl QuiltLoader {
+    fn install_dir(&self) -> String {
+        format!(
+            "versions/{}/{}-{}",
+            self.name(),
+            self.name(),
+            self.artifact_id()
+        )
+    }
+}
+
+fn meta_url() -> String {
+    format!("{}/v3/versions", config::get().upstreams.quilt_meta)
+}
+
+/// Lists one of Quilt's meta endpoints: `game`, `loader` or `installer`, newest first.
+async fn quilt_versions(component: &str) -> Result<Vec<String>, CloudError> {
+    let resp = fetch_json(&format!("{}/{}", meta_url(), component)).await?;
+    Ok(resp
+        .as_array()
+        .ok_or(CloudError::JSONError)?
+        .iter()
+        .filter_map(|v| v["version"].as_str().map(str::to_string))
+        .collect())
+}
+
+#[async_trait]
+impl LoaderBackend for QuiltLoader {
+    fn name(&self) -> &'static str {
+        "quilt"
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        Some(self.version.clone())
+    }
+
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::for_minecraft(&self.version)
+    }
+
+    fn artifact_id(&self) -> String {
+        format!("{}-{}", self.version.get(), self.loader_version)
+    }
+
+    fn content_dir(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    /// Quilt runs most Fabric mods as well.
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["quilt", "fabric"]
+    }
+
+    /// The newest installer, which takes the game and loader versions as arguments.
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        let installer = quilt_versions("installer")
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(CloudError::NoStableBuild)?;
+
+        Ok(ResolvedArtifact {
+            url: format!(
+                "{}/repository/release/org/quiltmc/quilt-installer/{}/quilt-installer-{}.jar",
+                config::get().upstreams.quilt_maven,
+                installer,
+                installer
+            ),
+            build: Some(self.loader_version.clone()),
+            checksum: None,
+        })
+    }
+
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        quilt_versions("game").await
+    }
+
+    /// Quilt loader versions are not tied to a game version, every one is listed.
+    async fn list_builds(&self, _version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+        let mut builds = quilt_versions("loader")
+            .await?
+            .into_iter()
+            .map(|build| LoaderBuild {
+                stable: !build.contains('-'),
+                build,
+            })
+            .collect::<Vec<_>>();
+        builds.reverse();
+        Ok(builds)
+    }
+
+    async fn validate(&self) -> Result<(), CloudError> {
+        ensure_listed(&self.upstream_versions().await?, self.version.get())?;
+        ensure_listed(&quilt_versions("loader").await?, &self.loader_version)
+    }
+
+    async fn install(&self) -> Result<(), CloudError> {
+        let install_dir = self.install_dir();
+        create_dir_all(&install_dir).map_err(|_| CloudError::FileError)?;
+
+        let artifact = self.resolve_artifact().await?;
+        let installer = format!("{}/installer.jar", install_dir);
+        download_file(&artifact.url, &installer, artifact.checksum.as_ref()).await?;
+
+        let runtime =
+            JavaRuntime::select(self.java_requirement(), self.java_version(), None, None);
+        ensure_java(&runtime).await?;
+        let java = canonicalize(runtime.java_path("."))
+            .map_err(|_| CloudError::FileError)?;
+
+        let status = Command::new(java)
+            .arg("-jar")
+            .arg("installer.jar")
+            .arg("install")
+            .arg("server")
+            .arg(self.version.get())
+            .arg(&self.loader_version)
+            .arg("--download-server")
+            .arg("--install-dir=.")
+            .current_dir(&install_dir)
+            .status()
+            .await
+            .map_err(|_| CloudError::InstallerError)?;
+
+        let _ = remove_file(&installer);
+        if !status.success() || !self.is_installed() {
+            return Err(CloudError::InstallerError);
+        }
+
+        Ok(())
+    }
+
+    fn is_installed(&self) -> bool {
+        Path::new(&self.install_dir()).join(LAUNCH_JAR).exists()
+    }
+
+    fn installed_build(&self) -> Option<String> {
+        Some(self.loader_version.clone())
+    }
+
+    /// The launcher finds its libraries next to itself but looks for `server.jar` in the
+    /// working directory, so the cached one is linked in.
+    fn prepare_instance(&self, dir: &str) -> Result<(), CloudError> {
+        let link = Path::new(dir).join("server.jar");
+        if symlink_metadata(&link).is_ok() {
+            remove_file(&link).map_err(|_| CloudError::FileError)?;
+        }
+        let server_jar = canonicalize(format!("{}/server.jar", self.install_dir()))
+            .map_err(|_| CloudError::FileError)?;
+        symlink(server_jar, link).map_err(|_| CloudError::FileError)
+    }
+
+    fn launch_args(&self, root: &str) -> Vec<String> {
+        vec![
+            "-jar".to_string(),
+            format!("{}/{}/{}", root, self.install_dir(), LAUNCH_JAR),
+            "nogui".to_string(),
+        ]
+    }
+}
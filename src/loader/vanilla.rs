@@ -0,0 +1,117 @@
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::{Checksum, download_file, fetch_json};
+use crate::java::{JavaRequirement, JavaVersion};
+use crate::loader::{LoaderBackend, ResolvedArtifact};
+use crate::minecraft_version::MinecraftVersion;
+use async_trait::async_trait;
+use std::fs::{create_dir_all, read_to_string};
+
+fn version_manifest_url() -> String {
+    format!(
+        "{}/mc/game/version_manifest_v2.json",
+        config::get().upstreams.mojang_meta
+    )
+}
+
+pub struct VanillaLoader {
+    pub version: MinecraftVersion,
+}
+
+impl VanillaLoader {
+    /// Finds this version in Mojang's manifest, returning the URL and SHA-1 of its version JSON.
+    async fn locate_version_json(&self) -> Result<(String, Checksum), CloudError> {
+        let manifest = fetch_json(&version_manifest_url()).await?;
+        let entry = manifest["versions"]
+            .as_array()
+            .ok_or(CloudError::JSONError)?
+            .iter()
+            .find(|v| v["id"].as_str() == Some(self.version.get()))
+            .ok_or(CloudError::NoArtifactForVersion)?;
+
+        let url = entry["url"].as_str().ok_or(CloudError::JSONError)?;
+        let sha1 = entry["sha1"].as_str().ok_or(CloudError::JSONError)?;
+        Ok((url.to_string(), Checksum::Sha1(sha1.to_string())))
+    }
+
+    fn cached_version_json(&self) -> Option<serde_json::Value> {
+        let content = read_to_string(self.artifact_path("json")).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// The Java the server ships with, as stated by the version JSON cached at install time.
+    fn manifest_java(&self) -> Option<JavaVersion> {
+        let major = self.cached_version_json()?["javaVersion"]["majorVersion"].as_u64()?;
+        Some(JavaVersion::new(major as u32))
+    }
+}
+
+fn server_artifact(version_json: &serde_json::Value) -> Result<ResolvedArtifact, CloudError> {
+    let server = &version_json["downloads"]["server"];
+    if server.is_null() {
+        return Err(CloudError::NoArtifactForVersion);
+    }
+
+    let url = server["url"].as_str().ok_or(CloudError::JSONError)?;
+    let sha1 = server["sha1"].as_str().ok_or(CloudError::JSONError)?;
+    Ok(ResolvedArtifact {
+        url: url.to_string(),
+        build: None,
+        checksum: Some(Checksum::Sha1(sha1.to_string())),
+    })
+}
+
+#[async_trait]
+impl LoaderBackend for VanillaLoader {
+    fn name(&self) -> &'static str {
+        "vanilla"
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        Some(self.version.clone())
+    }
+
+    /// The range table, raised to the Java Mojang ships the version with once it is known.
+    fn java_requirement(&self) -> JavaRequirement {
+        let requirement = JavaRequirement::for_minecraft(&self.version);
+        match self.manifest_java() {
+            Some(java) => requirement.with_min(java),
+            None => requirement,
+        }
+    }
+
+    fn java_version(&self) -> JavaVersion {
+        self.manifest_java()
+            .unwrap_or_else(|| self.java_requirement().preferred())
+    }
+
+    fn artifact_id(&self) -> String {
+        self.version.get().to_string()
+    }
+
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        let (url, _) = self.locate_version_json().await?;
+        server_artifact(&fetch_json(&url).await?)
+    }
+
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        let manifest = fetch_json(&version_manifest_url()).await?;
+        Ok(manifest["versions"]
+            .as_array()
+            .ok_or(CloudError::JSONError)?
+            .iter()
+            .filter_map(|v| v["id"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn install(&self) -> Result<(), CloudError> {
+        create_dir_all(format!("versions/{}", self.name())).map_err(|_| CloudError::FileError)?;
+
+        let (url, checksum) = self.locate_version_json().await?;
+        download_file(&url, &self.artifact_path("json"), Some(&checksum)).await?;
+
+        let version_json = self.cached_version_json().ok_or(CloudError::JSONError)?;
+        let artifact = server_artifact(&version_json)?;
+        download_file(&artifact.url, &self.jar_path(), artifact.checksum.as_ref()).await
+    }
+}
@@ -0,0 +1,101 @@
+use crate::config;
+use crate::config::ForwardingMode;
+use crate::errors::CloudError;
+use crate::instance::Instance;
+use crate::java::{JavaRequirement, JavaVersion};
+use crate::jvm;
+use crate::loader::config::PaperChannel;
+use crate::loader::paper::{
+    papermc_artifact_id, papermc_builds, papermc_versions, resolve_papermc_build,
+};
+use crate::loader::{LoaderBackend, LoaderBuild, ResolvedArtifact, ensure_listed};
+use crate::minecraft_version::MinecraftVersion;
+use crate::proxy::VELOCITY_CONFIG;
+use crate::server_files::{forwarding_secret, patch_toml};
+use async_trait::async_trait;
+use std::fs::write;
+
+const SECRET_FILE: &str = "forwarding.secret";
+
+pub struct VelocityLoader {
+    pub version: String,
+    pub build: Option<u32>,
+    pub channel: PaperChannel,
+}
+
+#[async_trait]
+impl LoaderBackend for VelocityLoader {
+    fn name(&self) -> &'static str {
+        "velocity"
+    }
+
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        None
+    }
+
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::at_least(JavaVersion::new(21))
+    }
+
+    fn artifact_id(&self) -> String {
+        papermc_artifact_id(&self.version, self.build, self.channel)
+    }
+
+    fn content_dir(&self) -> Option<&'static str> {
+        Some("plugins")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["velocity"]
+    }
+
+    fn hangar_platform(&self) -> Option<&'static str> {
+        Some("VELOCITY")
+    }
+
+    fn default_jvm_args(&self) -> Vec<String> {
+        jvm::velocity_flags()
+    }
+
+    fn write_config_files(&self, instance: &Instance, _proxied: bool) -> Result<(), CloudError> {
+        let dir = instance.directory();
+        let forwarding = config::get().proxy.forwarding;
+        let mode = match forwarding {
+            ForwardingMode::Modern => "modern",
+            ForwardingMode::Legacy => "legacy",
+            ForwardingMode::None => "none",
+        };
+
+        patch_toml(
+            &format!("{}/{}", dir, VELOCITY_CONFIG),
+            &[
+                ("bind", format!("\"0.0.0.0:{}\"", instance.port)),
+                ("show-max-players", instance.max_player.to_string()),
+                ("player-info-forwarding-mode", format!("\"{}\"", mode)),
+                ("forwarding-secret-file", format!("\"{}\"", SECRET_FILE)),
+            ],
+        )?;
+
+        if forwarding == ForwardingMode::Modern {
+            write(format!("{}/{}", dir, SECRET_FILE), forwarding_secret()?)
+                .map_err(|_| CloudError::FileError)?;
+        }
+        Ok(())
+    }
+
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        resolve_papermc_build("velocity", &self.version, self.build, self.channel).await
+    }
+
+    async fn upstream_versions(&self) -> Result<Vec<String>, CloudError> {
+        papermc_versions("velocity").await
+    }
+
+    async fn list_builds(&self, version: &str) -> Result<Vec<LoaderBuild>, CloudError> {
+        papermc_builds("velocity", version).await
+    }
+
+    async fn validate(&self) -> Result<(), CloudError> {
+        ensure_listed(&self.upstream_versions().await?, &self.version)
+    }
+}
@@ -1,11 +1,126 @@
+use crate::config;
 use crate::errors::CloudError;
-use crate::loader::LoaderBackend;
+use crate::file_downloader::{Checksum, exists, fetch_json_page, fetch_text};
+use crate::java::{JavaRequirement, JavaVersion};
+use crate::jvm;
+use crate::loader::{LoaderBackend, ResolvedArtifact};
 use crate::minecraft_version::MinecraftVersion;
-use crate::screen_manager::JavaVersion;
 use async_trait::async_trait;
 
+const RELEASES_PATH: &str = "repos/Kent1C38/Yggdrasil/releases";
+
 pub struct YggdrasilLoader {
     pub version: MinecraftVersion,
+    pub artifact_base_url: Option<String>,
+    pub release: Option<String>,
+}
+
+impl YggdrasilLoader {
+    /// Looks through the published releases, newest first or only the pinned one, for a jar
+    /// built against our version, a page of releases at a time.
+    async fn resolve_from_releases(&self) -> Result<ResolvedArtifact, CloudError> {
+        let mut next = Some(format!(
+            "{}/{}?per_page=100",
+            config::get().upstreams.github_api,
+            RELEASES_PATH
+        ));
+        while let Some(url) = next {
+            let (resp, next_url) = fetch_json_page(&url).await?;
+            next = next_url;
+            let releases = resp.as_array().ok_or(CloudError::JSONError)?;
+            if let Some(artifact) = self.find_in_releases(releases)? {
+                return Ok(artifact);
+            }
+        }
+
+        Err(CloudError::NoArtifactForVersion)
+    }
+
+    fn find_in_releases(
+        &self,
+        releases: &[serde_json::Value],
+    ) -> Result<Option<ResolvedArtifact>, CloudError> {
+        for release in releases {
+            if release["draft"].as_bool().unwrap_or(false)
+                || release["prerelease"].as_bool().unwrap_or(false)
+            {
+                continue;
+            }
+            if let Some(pinned) = &self.release
+                && release["tag_name"].as_str() != Some(pinned.as_str())
+            {
+                continue;
+            }
+
+            let tag_matches = release["tag_name"]
+                .as_str()
+                .is_some_and(|tag| names_version(tag, self.version.get()));
+
+            let Some(assets) = release["assets"].as_array() else {
+                continue;
+            };
+
+            let jars = assets.iter().filter(|asset| {
+                asset["name"].as_str().is_some_and(is_server_jar)
+            });
+
+            for asset in jars {
+                let name = asset["name"].as_str().unwrap_or_default();
+                if tag_matches || names_version(name.trim_end_matches(".jar"), self.version.get()) {
+                    let url = asset["browser_download_url"]
+                        .as_str()
+                        .ok_or(CloudError::JSONError)?;
+                    return Ok(Some(ResolvedArtifact {
+                        url: url.to_string(),
+                        build: release["tag_name"].as_str().map(str::to_string),
+                        checksum: asset["digest"]
+                            .as_str()
+                            .and_then(|digest| digest.strip_prefix("sha256:"))
+                            .map(|sha256| Checksum::Sha256(sha256.to_string())),
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn resolve_from_base_url(&self, base_url: &str) -> Result<ResolvedArtifact, CloudError> {
+        let url = format!(
+            "{}/yggdrasil-{}.jar",
+            base_url.trim_end_matches('/'),
+            self.version.get()
+        );
+
+        if !exists(&url).await? {
+            return Err(CloudError::NoArtifactForVersion);
+        }
+
+        let checksum = fetch_text(&format!("{}.sha256", url))
+            .await
+            .ok()
+            .and_then(|text| text.split_whitespace().next().map(str::to_string))
+            .map(Checksum::Sha256);
+
+        Ok(ResolvedArtifact {
+            url,
+            build: None,
+            checksum,
+        })
+    }
+}
+
+/// Skips the `-sources` and `-javadoc` jars published next to the server.
+fn is_server_jar(name: &str) -> bool {
+    name.strip_suffix(".jar").is_some_and(|stem| {
+        !stem.ends_with("-sources") && !stem.ends_with("-javadoc")
+    })
+}
+
+/// Whether a tag or file stem such as `v1.21.10` or `yggdrasil-1.21.10-all` refers to `version`.
+fn names_version(name: &str, version: &str) -> bool {
+    name.split(['-', '+', '_'])
+        .any(|part| part.trim_start_matches('v') == version)
 }
 
 #[async_trait]
@@ -14,16 +129,39 @@ impl LoaderBackend for YggdrasilLoader {
         "yggdrasil"
     }
 
-    fn version(&self) -> MinecraftVersion {
-        self.version.clone()
+    fn minecraft_version(&self) -> Option<MinecraftVersion> {
+        Some(self.version.clone())
     }
 
-    fn java_version(&self) -> JavaVersion {
-        JavaVersion::J25
+    /// Yggdrasil builds target the newest LTS whatever the game version.
+    fn java_requirement(&self) -> JavaRequirement {
+        JavaRequirement::at_least(JavaVersion::new(25))
     }
 
-    async fn resolve_download_url(&self) -> Result<String, CloudError> {
-        Ok("".to_string())
+    /// A pinned release gets its own jar.
+    fn artifact_id(&self) -> String {
+        match &self.release {
+            Some(release) => format!("{}-{}", self.version.get(), release),
+            None => self.version.get().to_string(),
+        }
+    }
+
+    fn default_jvm_args(&self) -> Vec<String> {
+        jvm::minestom_flags()
     }
-}
 
+    async fn resolve_artifact(&self) -> Result<ResolvedArtifact, CloudError> {
+        match &self.artifact_base_url {
+            Some(base_url) => self.resolve_from_base_url(base_url).await,
+            None => self.resolve_from_releases().await,
+        }
+    }
+
+    /// Releases are matched by name rather than listed, so validating means finding the artifact.
+    async fn validate(&self) -> Result<(), CloudError> {
+        match self.resolve_artifact().await {
+            Err(CloudError::NoArtifactForVersion) => Err(CloudError::UnknownVersion),
+            other => other.map(|_| ()),
+        }
+    }
+}
@@ -1,15 +1,28 @@
+mod cache;
+mod catalog;
+mod config;
 mod errors;
 mod file_downloader;
 mod heartbeat;
+mod installs;
 mod instance;
+mod java;
+mod jvm;
 mod minecraft_version;
+mod mrpack;
+mod plugins;
+mod proxy;
 mod screen_manager;
+mod server_files;
+mod upgrade;
 mod loader;
 
 use crate::errors::CloudError;
-use crate::instance::{Instance, create_instance, start_instance_status, stop_instance};
+use crate::instance::{
+    Instance, create_instance, get_launch_command, start_instance_status, stop_instance,
+};
 use axum;
-use axum::extract::State;
+use axum::extract::{DefaultBodyLimit, State};
 use axum::response::IntoResponse;
 use axum::routing::{delete, get, post};
 use axum::{Json, Router};
@@ -24,8 +37,17 @@ use tokio::net::TcpListener;
 use tokio::signal;
 use tokio::sync::{Mutex, oneshot};
 use crate::heartbeat::heartbeat_handler;
+use crate::catalog::{list_builds, list_loaders, list_versions};
+use crate::cache::{get_cache, prune_cache, start_prune_schedule};
+use crate::upgrade::upgrade_instances;
+use crate::mrpack::import_mrpack;
+use crate::plugins::{add_plugin, list_plugins, remove_plugin, sync_instance_plugins};
+use crate::java::jdk::clean_partial_installs;
+use crate::java::system::system_jdks;
 
 const PORT_RANGE: std::ops::Range<u16> = 25570..2999;
+/// Modpacks carry their overrides, often far beyond the default request body limit.
+const MAX_PACK_SIZE: usize = 512 * 1024 * 1024;
 
 #[derive(Serialize, Deserialize, Clone)]
 struct PortAvailability {
@@ -139,10 +161,20 @@ struct AppState {
 }
 
 fn init_cloud() -> Result<(), CloudError> {
+    config::load()?;
     create_dir_all("running/static").map_err(|_| CloudError::FileError)?;
     create_dir_all("templates").map_err(|_| CloudError::FileError)?;
     create_dir_all("versions").map_err(|_| CloudError::FileError)?;
-    create_dir_all("running/disposable").map_err(|_| CloudError::FileError)
+    create_dir_all("running/disposable").map_err(|_| CloudError::FileError)?;
+
+    let removed = clean_partial_installs();
+    if removed > 0 {
+        println!("Removed {} partial JDK installs", removed);
+    }
+    if config::get().java.prefer_system {
+        println!("Found {} system JDKs", system_jdks().len());
+    }
+    Ok(())
 }
 
 #[tokio::main]
@@ -158,6 +190,7 @@ async fn main() -> Result<(), CloudError> {
 
     let daemon = Arc::new(Mutex::new(Daemon::load_or_default()));
     let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    start_prune_schedule(daemon.clone());
 
     let app_state = AppState {
         daemon: daemon.clone(),
@@ -183,7 +216,21 @@ async fn main() -> Result<(), CloudError> {
         .route("/start/{name}", post(start_instance_status))
         .route("/shutdown", post(shutdown))
         .route("/register", post(create_instance))
+        .route(
+            "/import/mrpack",
+            post(import_mrpack).layer(DefaultBodyLimit::max(MAX_PACK_SIZE)),
+        )
+        .route("/command/{name}", get(get_launch_command))
+        .route("/plugins/{name}", get(list_plugins).post(add_plugin))
+        .route("/sync/{name}", post(sync_instance_plugins))
+        .route("/plugins/{name}/{plugin}", delete(remove_plugin))
         .route("/heartbeat/{name}", post(heartbeat_handler))
+        .route("/upgrade", post(upgrade_instances))
+        .route("/cache", get(get_cache))
+        .route("/cache/prune", post(prune_cache))
+        .route("/loaders", get(list_loaders))
+        .route("/loaders/{name}/versions", get(list_versions))
+        .route("/loaders/{name}/versions/{version}/builds", get(list_builds))
         .with_state(app_state);
 
     let listener = TcpListener::bind("0.0.0.0:3001").await.unwrap();
@@ -1,19 +1,308 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A Minecraft version as published by Mojang, kept verbatim and parsed for comparisons.
+///
+/// Releases (`1.8.8`, `1.21.10`, `26.1`), their pre-releases and release candidates
+/// (`1.21-pre1`, `1.21.5-rc2`, `26.1-pre-1`) and weekly snapshots (`24w14a`) are understood.
+/// Snapshots only compare with each other, as their position among releases is not encoded.
+/// Any other id, e.g. `1.14 Pre-Release 1` or `b1.7.3`, is kept as is and compares with nothing,
+/// upstream version lists decide whether a loader serves it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MinecraftVersion {
+    raw: String,
+    kind: VersionKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum VersionKind {
+    Release {
+        major: u32,
+        minor: u32,
+        patch: u32,
+        stage: Stage,
+    },
+    Snapshot {
+        year: u32,
+        week: u32,
+        revision: char,
+    },
+    Other,
+}
+
+/// Declaration order is release order, a final release comes after its candidates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Snapshot(u32),
+    PreRelease(u32),
+    ReleaseCandidate(u32),
+    Final,
+}
 
-#[derive(Deserialize, Serialize, Clone)]
 #[derive(Debug)]
-pub enum MinecraftVersion {
-    #[serde(rename = "1.21.10")]
-    V1_21_10,
-    #[serde(rename = "1.21.11")]
-    V1_21_11,
+pub struct InvalidVersion(pub String);
+
+impl fmt::Display for InvalidVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a Minecraft version", self.0)
+    }
 }
 
 impl MinecraftVersion {
-    pub fn get(&self) -> &'static str {
-        match self {
-            MinecraftVersion::V1_21_11 => "1.21.11",
-            MinecraftVersion::V1_21_10 => "1.21.10",
+    /// Only fails on blank ids, unrecognised ones are kept as is.
+    pub fn parse(raw: &str) -> Result<Self, InvalidVersion> {
+        if raw.trim().is_empty() {
+            return Err(InvalidVersion(raw.to_string()));
+        }
+        let kind = parse_snapshot(raw)
+            .or_else(|| parse_release(raw))
+            .unwrap_or(VersionKind::Other);
+        Ok(Self {
+            raw: raw.to_string(),
+            kind,
+        })
+    }
+
+    pub fn get(&self) -> &str {
+        &self.raw
+    }
+
+    /// `(major, minor, patch)` of a release line, `None` for weekly snapshots and unrecognised ids.
+    pub fn release(&self) -> Option<(u32, u32, u32)> {
+        match self.kind {
+            VersionKind::Release {
+                major,
+                minor,
+                patch,
+                ..
+            } => Some((major, minor, patch)),
+            VersionKind::Snapshot { .. } | VersionKind::Other => None,
+        }
+    }
+
+    pub fn is_stable(&self) -> bool {
+        matches!(
+            self.kind,
+            VersionKind::Release {
+                stage: Stage::Final,
+                ..
+            }
+        )
+    }
+}
+
+fn parse_snapshot(raw: &str) -> Option<VersionKind> {
+    let (year, rest) = raw.split_once('w')?;
+    if year.len() != 2 || rest.len() != 3 {
+        return None;
+    }
+    let revision = rest.chars().last()?;
+    if !revision.is_ascii_lowercase() {
+        return None;
+    }
+    Some(VersionKind::Snapshot {
+        year: parse_number(year)?,
+        week: parse_number(&rest[..2])?,
+        revision,
+    })
+}
+
+fn parse_release(raw: &str) -> Option<VersionKind> {
+    let (numbers, suffix) = match raw.split_once('-') {
+        Some((numbers, suffix)) => (numbers, Some(suffix)),
+        None => (raw, None),
+    };
+
+    let mut parts = numbers.split('.');
+    let major = parse_number(parts.next()?)?;
+    let minor = parse_number(parts.next()?)?;
+    let patch = match parts.next() {
+        Some(patch) => parse_number(patch)?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let stage = match suffix {
+        None => Stage::Final,
+        Some(suffix) => parse_stage(suffix)?,
+    };
+
+    Some(VersionKind::Release {
+        major,
+        minor,
+        patch,
+        stage,
+    })
+}
+
+/// Accepts both the historic `pre1`/`rc1` and the newer `pre-1`/`rc-1`/`snapshot-1` suffixes.
+fn parse_stage(suffix: &str) -> Option<Stage> {
+    let (label, number) = match suffix.split_once('-') {
+        Some((label, number)) => (label, number),
+        None => suffix.split_at(suffix.find(|c: char| c.is_ascii_digit())?),
+    };
+    let number = parse_number(number)?;
+    match label {
+        "snapshot" => Some(Stage::Snapshot(number)),
+        "pre" => Some(Stage::PreRelease(number)),
+        "rc" => Some(Stage::ReleaseCandidate(number)),
+        _ => None,
+    }
+}
+
+fn parse_number(raw: &str) -> Option<u32> {
+    if raw.is_empty() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    raw.parse().ok()
+}
+
+impl TryFrom<String> for MinecraftVersion {
+    type Error = InvalidVersion;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        Self::parse(&raw)
+    }
+}
+
+impl From<MinecraftVersion> for String {
+    fn from(version: MinecraftVersion) -> Self {
+        version.raw
+    }
+}
+
+impl fmt::Display for MinecraftVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl PartialEq for MinecraftVersion {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.kind, &other.kind) {
+            (VersionKind::Other, VersionKind::Other) => self.raw == other.raw,
+            (kind, other_kind) => kind == other_kind,
+        }
+    }
+}
+
+impl PartialOrd for MinecraftVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (&self.kind, &other.kind) {
+            (
+                VersionKind::Release {
+                    major,
+                    minor,
+                    patch,
+                    stage,
+                },
+                VersionKind::Release {
+                    major: other_major,
+                    minor: other_minor,
+                    patch: other_patch,
+                    stage: other_stage,
+                },
+            ) => Some(
+                (major, minor, patch, stage).cmp(&(other_major, other_minor, other_patch, other_stage)),
+            ),
+            (
+                VersionKind::Snapshot {
+                    year,
+                    week,
+                    revision,
+                },
+                VersionKind::Snapshot {
+                    year: other_year,
+                    week: other_week,
+                    revision: other_revision,
+                },
+            ) => Some((year, week, revision).cmp(&(other_year, other_week, other_revision))),
+            (VersionKind::Other, VersionKind::Other) if self.raw == other.raw => {
+                Some(Ordering::Equal)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(raw: &str) -> MinecraftVersion {
+        MinecraftVersion::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn parses_releases() {
+        assert_eq!(version("1.8.8").release(), Some((1, 8, 8)));
+        assert_eq!(version("1.21").release(), Some((1, 21, 0)));
+        assert_eq!(version("26.1").release(), Some((26, 1, 0)));
+        assert!(version("1.21.10").is_stable());
+    }
+
+    #[test]
+    fn parses_both_pre_release_spellings() {
+        assert_eq!(version("1.21-pre1").release(), Some((1, 21, 0)));
+        assert_eq!(version("26.1-pre-1").release(), Some((26, 1, 0)));
+        assert!(!version("1.21.5-rc2").is_stable());
+        assert!(!version("26.1-snapshot-1").is_stable());
+    }
+
+    #[test]
+    fn orders_stages_before_their_release() {
+        assert!(version("26.1-snapshot-2") < version("26.1-pre-1"));
+        assert!(version("1.21-pre1") < version("1.21-pre2"));
+        assert!(version("1.21-pre4") < version("1.21-rc1"));
+        assert!(version("1.21-rc1") < version("1.21"));
+        assert!(version("1.21") < version("1.21.1-pre1"));
+    }
+
+    #[test]
+    fn orders_releases_numerically() {
+        assert!(version("1.9") < version("1.10"));
+        assert!(version("1.21.9") < version("1.21.10"));
+        assert!(version("1.21.10") < version("26.1"));
+        assert_eq!(version("1.21"), version("1.21.0"));
+    }
+
+    #[test]
+    fn snapshots_only_compare_with_snapshots() {
+        assert!(version("24w14a").release().is_none());
+        assert!(version("24w13a") < version("24w14a"));
+        assert!(version("24w14a") < version("24w14b"));
+        assert!(version("24w14a").partial_cmp(&version("1.20.5")).is_none());
+    }
+
+    #[test]
+    fn keeps_unrecognised_ids_verbatim() {
+        for raw in [
+            "1.14 Pre-Release 1",
+            "b1.7.3",
+            "1.RV-Pre1",
+            "3D Shareware v1.34",
+        ] {
+            let parsed = version(raw);
+            assert_eq!(parsed.get(), raw);
+            assert!(parsed.release().is_none());
+            assert!(!parsed.is_stable());
+            assert_eq!(parsed, version(raw));
+            assert!(parsed.partial_cmp(&version("1.14")).is_none());
         }
+        assert_ne!(version("b1.7.3"), version("1.RV-Pre1"));
+        assert!(MinecraftVersion::parse(" ").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let parsed: MinecraftVersion = serde_json::from_str("\"1.14 Pre-Release 1\"").unwrap();
+        assert_eq!(
+            serde_json::to_string(&parsed).unwrap(),
+            "\"1.14 Pre-Release 1\""
+        );
     }
 }
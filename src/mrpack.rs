@@ -0,0 +1,410 @@
+use crate::errors::CloudError;
+use crate::file_downloader::{Checksum, download_file, file_sha256, sha256_hex};
+use crate::installs::ensure_cached;
+use crate::instance::{Instance, InstanceRole, register_instance};
+use crate::java::provider::JdkVendor;
+use crate::loader::build_loader;
+use crate::loader::config::LoaderConfig;
+use crate::loader::fabric::latest_stable_installer;
+use crate::minecraft_version::MinecraftVersion;
+use crate::plugins::{InstalledPlugin, PluginSource, hashed_cache_dir, is_safe_file_name};
+use crate::server_files::patch_properties;
+use crate::{AppState, Daemon};
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, remove_dir_all, rename, write};
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use zip::ZipArchive;
+
+const INDEX_FILE: &str = "modrinth.index.json";
+/// Applied in this order, server-specific overrides win.
+const OVERRIDE_DIRS: [&str; 2] = ["overrides", "server-overrides"];
+
+/// Settings of the instance created from the pack, the rest comes from the pack itself.
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    server_id: String,
+    server_name: Option<String>,
+    #[serde(default)]
+    is_persistent: bool,
+    group: Option<String>,
+    #[serde(default)]
+    behind_proxy: bool,
+    port: u16,
+    max_player: u16,
+    memory_mb: Option<u32>,
+    jdk_vendor: Option<JdkVendor>,
+}
+
+#[derive(Deserialize)]
+struct PackIndex {
+    game: String,
+    name: String,
+    #[serde(rename = "versionId")]
+    version_id: String,
+    files: Vec<PackFile>,
+    dependencies: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct PackFile {
+    path: String,
+    hashes: HashMap<String, String>,
+    #[serde(default)]
+    env: Option<HashMap<String, String>>,
+    downloads: Vec<String>,
+}
+
+impl PackFile {
+    fn on_server(&self) -> bool {
+        self.env
+            .as_ref()
+            .and_then(|env| env.get("server"))
+            .is_none_or(|server| server != "unsupported")
+    }
+
+    fn checksum(&self) -> Result<Checksum, CloudError> {
+        if let Some(sha512) = self.hashes.get("sha512") {
+            Ok(Checksum::Sha512(sha512.clone()))
+        } else if let Some(sha1) = self.hashes.get("sha1") {
+            Ok(Checksum::Sha1(sha1.clone()))
+        } else {
+            Err(CloudError::JSONError)
+        }
+    }
+}
+
+/// What the archive holds, read up front so nothing borrows the zip across downloads.
+struct Pack {
+    index: PackIndex,
+    overrides: Vec<(PathBuf, Vec<u8>)>,
+}
+
+/// A path from the pack must stay inside the instance directory.
+fn relative_path(path: &str) -> Result<PathBuf, CloudError> {
+    let path = PathBuf::from(path);
+    if path.as_os_str().is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(CloudError::InvalidArtifactName);
+    }
+    Ok(path)
+}
+
+fn read_pack(bytes: Bytes) -> Result<Pack, CloudError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|_| CloudError::JSONError)?;
+
+    let index = {
+        let entry = archive
+            .by_name(INDEX_FILE)
+            .map_err(|_| CloudError::JSONError)?;
+        serde_json::from_reader::<_, PackIndex>(entry).map_err(|_| CloudError::JSONError)?
+    };
+
+    let mut overrides = Vec::new();
+    for dir in OVERRIDE_DIRS {
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|_| CloudError::FileError)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let Some(path) = entry.enclosed_name() else {
+                return Err(CloudError::InvalidArtifactName);
+            };
+            let Ok(relative) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let relative = relative.to_path_buf();
+            let mut content = Vec::new();
+            entry
+                .read_to_end(&mut content)
+                .map_err(|_| CloudError::FileError)?;
+            overrides.push((relative, content));
+        }
+    }
+
+    Ok(Pack { index, overrides })
+}
+
+/// Picks the loader from the pack dependencies, Fabric packs do not name an installer.
+async fn pack_loader(index: &PackIndex) -> Result<LoaderConfig, CloudError> {
+    if index.game != "minecraft" {
+        return Err(CloudError::Unsupported);
+    }
+    let version = index
+        .dependencies
+        .get("minecraft")
+        .and_then(|v| MinecraftVersion::parse(v).ok())
+        .ok_or(CloudError::UnknownVersion)?;
+    let dependency = |name: &str| index.dependencies.get(name).cloned();
+
+    if let Some(loader_version) = dependency("fabric-loader") {
+        Ok(LoaderConfig::Fabric {
+            version,
+            loader_version,
+            installer_version: latest_stable_installer().await?,
+        })
+    } else if let Some(loader_version) = dependency("quilt-loader") {
+        Ok(LoaderConfig::Quilt {
+            version,
+            loader_version,
+        })
+    } else if let Some(loader_version) = dependency("neoforge") {
+        Ok(LoaderConfig::NeoForge {
+            version,
+            loader_version,
+        })
+    } else if let Some(loader_version) = dependency("forge") {
+        Ok(LoaderConfig::Forge {
+            version,
+            loader_version,
+        })
+    } else {
+        Err(CloudError::Unsupported)
+    }
+}
+
+/// A jar directly inside the loader's content folder, managed through the lockfile.
+fn content_jar<'a>(path: &'a Path, content_dir: &str) -> Option<&'a str> {
+    let mut components = path.components();
+    let (Some(Component::Normal(dir)), Some(Component::Normal(file)), None) =
+        (components.next(), components.next(), components.next())
+    else {
+        return None;
+    };
+    let file = file.to_str()?;
+    (dir == content_dir && file.ends_with(".jar")).then_some(file)
+}
+
+async fn download_any(urls: &[String], path: &str, checksum: &Checksum) -> Result<(), CloudError> {
+    let mut result = Err(CloudError::DownloadError);
+    for url in urls {
+        result = download_file(url, path, Some(checksum)).await;
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+/// Caches the mod from the first of its download URLs that works, like `download_any`.
+async fn cache_mod(file: &PackFile, file_name: &str) -> Result<InstalledPlugin, CloudError> {
+    let checksum = file.checksum()?;
+    let mut result = Err(CloudError::NoArtifactForVersion);
+    for url in &file.downloads {
+        let cached = format!("{}/{}", hashed_cache_dir("url", url), file_name);
+        result = ensure_cached(&cached, url, Some(&checksum))
+            .await
+            .map(|()| (url, cached));
+        if result.is_ok() {
+            break;
+        }
+    }
+    let (url, cached) = result?;
+
+    let source = PluginSource::Url {
+        url: url.clone(),
+        name: Some(file_name.trim_end_matches(".jar").to_lowercase()),
+        checksum: None,
+    };
+    Ok(InstalledPlugin {
+        name: source.name(),
+        source,
+        version: None,
+        url: Some(url.clone()),
+        sha256: file_sha256(&cached).await?,
+        file: cached,
+    })
+}
+
+/// Keyed by content, so a changed pack never overwrites a jar other instances link to.
+fn cache_override_jar(file_name: &str, content: &[u8], sha256: &str) -> Result<String, CloudError> {
+    let dir = hashed_cache_dir("mrpack", sha256);
+    create_dir_all(&dir).map_err(|_| CloudError::FileError)?;
+    let cached = format!("{}/{}", dir, file_name);
+    if !Path::new(&cached).exists() {
+        let temp_path = format!("{}.part", cached);
+        write(&temp_path, content).map_err(|_| CloudError::FileError)?;
+        rename(&temp_path, &cached).map_err(|_| CloudError::FileError)?;
+    }
+    Ok(cached)
+}
+
+/// Caches every mod of the pack and lists them the way the lockfile records them.
+async fn cache_mods(pack: &Pack, content_dir: &str) -> Result<Vec<InstalledPlugin>, CloudError> {
+    let mut mods = Vec::new();
+    for file in pack.index.files.iter().filter(|f| f.on_server()) {
+        let path = relative_path(&file.path)?;
+        if let Some(file_name) = content_jar(&path, content_dir) {
+            mods.push(cache_mod(file, file_name).await?);
+        }
+    }
+
+    for (path, content) in &pack.overrides {
+        let Some(file_name) = content_jar(path, content_dir) else {
+            continue;
+        };
+        if !is_safe_file_name(file_name) {
+            return Err(CloudError::InvalidArtifactName);
+        }
+        let sha256 = sha256_hex(content);
+        let cached = cache_override_jar(file_name, content, &sha256)?;
+        // An override replaces a downloaded mod of the same name.
+        mods.retain(|m| !m.file.ends_with(&format!("/{}", file_name)));
+        mods.push(InstalledPlugin {
+            name: file_name.trim_end_matches(".jar").to_lowercase(),
+            source: PluginSource::Mrpack {
+                pack: pack.index.name.clone(),
+            },
+            version: Some(pack.index.version_id.clone()),
+            url: None,
+            sha256,
+            file: cached,
+        });
+    }
+    Ok(mods)
+}
+
+/// Downloads the remaining server files and applies the overrides into the instance directory.
+async fn unpack(pack: &Pack, dir: &str, content_dir: &str) -> Result<(), CloudError> {
+    for file in pack.index.files.iter().filter(|f| f.on_server()) {
+        let path = relative_path(&file.path)?;
+        if content_jar(&path, content_dir).is_some() {
+            continue;
+        }
+        let target = Path::new(dir).join(&path);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent).map_err(|_| CloudError::FileError)?;
+        }
+        download_any(&file.downloads, &target.to_string_lossy(), &file.checksum()?).await?;
+    }
+
+    for (path, content) in &pack.overrides {
+        if content_jar(path, content_dir).is_some() {
+            continue;
+        }
+        let target = Path::new(dir).join(path);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent).map_err(|_| CloudError::FileError)?;
+        }
+        write(&target, content).map_err(|_| CloudError::FileError)?;
+    }
+    Ok(())
+}
+
+/// Forgets an instance whose import failed half-way, so the import can be retried.
+async fn unregister(daemon: &Arc<Mutex<Daemon>>, instance: &Instance) {
+    let mut guard = daemon.lock().await;
+    let mut kept = Vec::new();
+    for inst in guard.server_list.drain(..) {
+        if inst.lock().await.server_id != instance.server_id {
+            kept.push(inst);
+        }
+    }
+    guard.server_list = kept;
+    let _ = remove_dir_all(instance.directory());
+}
+
+async fn import(
+    daemon: &Arc<Mutex<Daemon>>,
+    query: ImportQuery,
+    bytes: Bytes,
+) -> Result<Instance, CloudError> {
+    let pack = read_pack(bytes)?;
+    let loader_config = pack_loader(&pack.index).await?;
+    let content_dir = build_loader(&loader_config)
+        .content_dir()
+        .ok_or(CloudError::Unsupported)?;
+
+    let plugins = cache_mods(&pack, content_dir).await?;
+    let instance = Instance {
+        server_name: query.server_name.unwrap_or_else(|| pack.index.name.clone()),
+        server_id: query.server_id,
+        is_persistent: query.is_persistent,
+        group: query.group,
+        role: InstanceRole::Backend,
+        behind_proxy: query.behind_proxy,
+        loader: loader_config,
+        loader_build: None,
+        port: query.port,
+        max_player: query.max_player,
+        memory_mb: query.memory_mb,
+        jvm_args: Vec::new(),
+        skip_default_jvm_args: false,
+        jdk_vendor: query.jdk_vendor,
+        jdk_release: None,
+        jdk_version: None,
+        plugins,
+        started: false,
+        heartbeat_started: false,
+        last_heartbeat: 0,
+    };
+    register_instance(daemon.clone(), instance.clone()).await?;
+
+    // Server packs often ship their own server.properties, which must not pick the port.
+    let dir = instance.directory();
+    let unpacked = unpack(&pack, &dir, content_dir).await.and_then(|()| {
+        patch_properties(
+            &format!("{}/server.properties", dir),
+            &[
+                ("server-port", instance.port.to_string()),
+                ("max-players", instance.max_player.to_string()),
+            ],
+        )
+    });
+    if let Err(e) = unpacked {
+        unregister(daemon, &instance).await;
+        return Err(e);
+    }
+    Ok(instance)
+}
+
+pub async fn import_mrpack(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    bytes: Bytes,
+) -> impl IntoResponse {
+    match import(&state.daemon, query, bytes).await {
+        Ok(instance) => (StatusCode::CREATED, Json(instance)).into_response(),
+        Err(CloudError::JSONError) => {
+            (StatusCode::BAD_REQUEST, "Not a valid Modrinth modpack").into_response()
+        }
+        Err(CloudError::InvalidArtifactName) => {
+            (StatusCode::BAD_REQUEST, "The modpack contains an unsafe file path").into_response()
+        }
+        Err(CloudError::Unsupported) => (
+            StatusCode::BAD_REQUEST,
+            "The modpack needs a loader that is not supported",
+        )
+            .into_response(),
+        Err(CloudError::UnknownVersion) => (
+            StatusCode::BAD_REQUEST,
+            "This version is not available for this loader",
+        )
+            .into_response(),
+        Err(CloudError::InstanceAlreadyExists) => {
+            (StatusCode::CONFLICT, "An instance with this id already exists").into_response()
+        }
+        Err(CloudError::HTTPError) | Err(CloudError::DownloadError) => (
+            StatusCode::BAD_GATEWAY,
+            "Could not reach the loader or file upstream",
+        )
+            .into_response(),
+        Err(CloudError::ChecksumMismatch) => (
+            StatusCode::BAD_GATEWAY,
+            "A modpack file failed verification",
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Could not import the modpack",
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,518 @@
+use crate::AppState;
+use crate::config;
+use crate::errors::CloudError;
+use crate::file_downloader::{Checksum, fetch_json, file_sha256, short_hash};
+use crate::installs::ensure_cached;
+use crate::instance::Instance;
+use crate::loader::{LoaderBackend, build_loader};
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{
+    canonicalize, create_dir_all, read_dir, read_to_string, remove_file, symlink_metadata, write,
+};
+use std::os::unix::fs::symlink;
+use std::path::Component;
+
+pub const PLUGINS_DIR: &str = "plugins";
+const LOCKFILE: &str = "aesir.lock";
+
+/// Where a plugin or mod comes from, as sent when adding it to an instance.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PluginSource {
+    /// A project slug on Hangar, the newest compatible version unless one is pinned.
+    Hangar {
+        project: String,
+        #[serde(default)]
+        version: Option<String>,
+    },
+    /// A project slug or id on Modrinth, the newest compatible version unless one is pinned.
+    Modrinth {
+        project: String,
+        #[serde(default)]
+        version: Option<String>,
+    },
+    /// A jar published anywhere else, which cannot be checked for compatibility.
+    Url {
+        url: String,
+        #[serde(default)]
+        name: Option<String>,
+        /// SHA-256 of the jar.
+        #[serde(default)]
+        checksum: Option<String>,
+    },
+    /// Shipped inside a Modrinth modpack's overrides, so it only exists in the cache.
+    Mrpack { pack: String },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InstalledPlugin {
+    pub name: String,
+    pub source: PluginSource,
+    /// The version the source resolved to, unknown for plain URLs.
+    pub version: Option<String>,
+    /// Where the jar was downloaded from, so a sync never has to resolve the source again.
+    pub url: Option<String>,
+    /// The jar in the shared cache, linked into the instance's content folder.
+    pub file: String,
+    pub sha256: String,
+}
+
+/// `aesir.lock`, the exact plugins an instance runs with, next to its `aesir.config`.
+#[derive(Serialize, Deserialize, Default)]
+struct Lockfile {
+    plugins: Vec<InstalledPlugin>,
+}
+
+#[derive(Serialize)]
+pub struct SyncReport {
+    linked: Vec<String>,
+    removed: Vec<String>,
+}
+
+struct ResolvedPlugin {
+    version: Option<String>,
+    url: String,
+    file_name: String,
+    cache_dir: String,
+    checksum: Option<Checksum>,
+}
+
+impl PluginSource {
+    /// The name a plugin is listed and removed by.
+    pub fn name(&self) -> String {
+        match self {
+            PluginSource::Hangar { project, .. } | PluginSource::Modrinth { project, .. } => {
+                project.to_lowercase()
+            }
+            PluginSource::Mrpack { pack } => pack.to_lowercase(),
+            PluginSource::Url { url, name, .. } => name.clone().unwrap_or_else(|| {
+                url_file_name(url)
+                    .trim_end_matches(".jar")
+                    .to_lowercase()
+            }),
+        }
+    }
+
+    async fn resolve(&self, loader: &dyn LoaderBackend) -> Result<ResolvedPlugin, CloudError> {
+        match self {
+            PluginSource::Hangar { project, .. } | PluginSource::Modrinth { project, .. }
+                if !is_safe_file_name(project) =>
+            {
+                Err(CloudError::InvalidArtifactName)
+            }
+            PluginSource::Hangar { project, version } => {
+                resolve_hangar(loader, project, version.as_deref()).await
+            }
+            PluginSource::Modrinth { project, version } => {
+                resolve_modrinth(loader, project, version.as_deref()).await
+            }
+            PluginSource::Url { url, checksum, .. } => Ok(ResolvedPlugin {
+                version: None,
+                url: url.clone(),
+                file_name: url_file_name(url),
+                cache_dir: hashed_cache_dir("url", url),
+                checksum: checksum.clone().map(Checksum::Sha256),
+            }),
+            PluginSource::Mrpack { .. } => Err(CloudError::Unsupported),
+        }
+    }
+}
+
+/// A cache folder named after a hash of `key`, for jars without a project to file them under.
+pub fn hashed_cache_dir(kind: &str, key: &str) -> String {
+    format!("{}/{}/{}", PLUGINS_DIR, kind, short_hash(key))
+}
+
+fn url_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name = path.rsplit('/').next().unwrap_or_default();
+    if name.ends_with(".jar") {
+        name.to_string()
+    } else {
+        format!("{}.jar", name)
+    }
+}
+
+/// Keeps a file name from upstream metadata from escaping the cache folder.
+pub fn is_safe_file_name(name: &str) -> bool {
+    !name.is_empty() && name != ".." && !name.contains(['/', '\\'])
+}
+
+fn contains(values: &Value, wanted: &str) -> bool {
+    values
+        .as_array()
+        .is_some_and(|values| values.iter().any(|v| v.as_str() == Some(wanted)))
+}
+
+async fn resolve_modrinth(
+    loader: &dyn LoaderBackend,
+    project: &str,
+    pinned: Option<&str>,
+) -> Result<ResolvedPlugin, CloudError> {
+    if loader.modrinth_loaders().is_empty() {
+        return Err(CloudError::Unsupported);
+    }
+
+    let url = format!(
+        "{}/v2/project/{}/version",
+        config::get().upstreams.modrinth_api,
+        project
+    );
+    let resp = fetch_json(&url).await?;
+    let versions = resp.as_array().ok_or(CloudError::JSONError)?;
+
+    let candidates = versions
+        .iter()
+        .filter(|v| pinned.is_none_or(|pinned| v["id"] == pinned || v["version_number"] == pinned))
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Err(CloudError::NoArtifactForVersion);
+    }
+
+    // Versions are listed newest first.
+    let version = candidates
+        .into_iter()
+        .find(|v| {
+            let loader_matches = loader
+                .modrinth_loaders()
+                .iter()
+                .any(|name| contains(&v["loaders"], name));
+            let game_matches = loader
+                .minecraft_version()
+                .is_none_or(|mc| contains(&v["game_versions"], mc.get()));
+            loader_matches && game_matches
+        })
+        .ok_or(CloudError::Incompatible)?;
+
+    let files = version["files"].as_array().ok_or(CloudError::JSONError)?;
+    let file = files
+        .iter()
+        .find(|f| f["primary"].as_bool().unwrap_or(false))
+        .or_else(|| files.first())
+        .ok_or(CloudError::NoArtifactForVersion)?;
+
+    Ok(ResolvedPlugin {
+        version: version["version_number"].as_str().map(str::to_string),
+        url: file["url"].as_str().ok_or(CloudError::JSONError)?.to_string(),
+        file_name: file["filename"].as_str().ok_or(CloudError::JSONError)?.to_string(),
+        cache_dir: format!("{}/modrinth/{}", PLUGINS_DIR, project.to_lowercase()),
+        checksum: file["hashes"]["sha1"]
+            .as_str()
+            .map(|sha1| Checksum::Sha1(sha1.to_string())),
+    })
+}
+
+async fn resolve_hangar(
+    loader: &dyn LoaderBackend,
+    project: &str,
+    pinned: Option<&str>,
+) -> Result<ResolvedPlugin, CloudError> {
+    let platform = loader.hangar_platform().ok_or(CloudError::Unsupported)?;
+    let base = format!(
+        "{}/api/v1/projects/{}/versions",
+        config::get().upstreams.hangar_api,
+        project
+    );
+
+    let versions = match pinned {
+        Some(pinned) => vec![fetch_json(&format!("{}/{}", base, pinned)).await?],
+        None => {
+            let url = format!("{}?limit=25&offset=0&platform={}", base, platform);
+            let resp = fetch_json(&url).await?;
+            resp["result"].as_array().ok_or(CloudError::JSONError)?.clone()
+        }
+    };
+    if versions.is_empty() {
+        return Err(CloudError::NoArtifactForVersion);
+    }
+
+    // Proxies run any game version, their platform version is not tracked here.
+    let version = versions
+        .iter()
+        .find(|v| {
+            !v["downloads"][platform].is_null()
+                && loader
+                    .minecraft_version()
+                    .is_none_or(|mc| contains(&v["platformDependencies"][platform], mc.get()))
+        })
+        .ok_or(CloudError::Incompatible)?;
+
+    let name = version["name"].as_str().ok_or(CloudError::JSONError)?;
+    let download = &version["downloads"][platform];
+    let url = download["downloadUrl"]
+        .as_str()
+        .or_else(|| download["externalUrl"].as_str())
+        .ok_or(CloudError::NoArtifactForVersion)?;
+
+    Ok(ResolvedPlugin {
+        version: Some(name.to_string()),
+        url: url.to_string(),
+        file_name: download["fileInfo"]["name"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}-{}.jar", project, name)),
+        cache_dir: format!("{}/hangar/{}", PLUGINS_DIR, project.to_lowercase()),
+        checksum: download["fileInfo"]["sha256Hash"]
+            .as_str()
+            .map(|sha256| Checksum::Sha256(sha256.to_string())),
+    })
+}
+
+fn link_path(instance: &Instance, content_dir: &str, file: &str) -> String {
+    let file_name = file.rsplit('/').next().unwrap_or(file);
+    format!("{}/{}/{}", instance.directory(), content_dir, file_name)
+}
+
+/// Points the instance's content folder at the cached jar, replacing any stale link.
+pub fn link_plugin(instance: &Instance, content_dir: &str, file: &str) -> Result<(), CloudError> {
+    let link = link_path(instance, content_dir, file);
+    if symlink_metadata(&link).is_ok() {
+        remove_file(&link).map_err(|_| CloudError::FileError)?;
+    }
+    create_dir_all(format!("{}/{}", instance.directory(), content_dir))
+        .map_err(|_| CloudError::FileError)?;
+    let cached = canonicalize(file).map_err(|_| CloudError::FileError)?;
+    symlink(cached, link).map_err(|_| CloudError::FileError)
+}
+
+/// Resolves `source` for the instance's loader and caches the jar, without touching the instance.
+pub async fn fetch_plugin(
+    instance: &Instance,
+    source: PluginSource,
+) -> Result<InstalledPlugin, CloudError> {
+    let loader = build_loader(&instance.loader);
+    if loader.content_dir().is_none() {
+        return Err(CloudError::Unsupported);
+    }
+
+    let resolved = source.resolve(loader.as_ref()).await?;
+    if !is_safe_file_name(&resolved.file_name) {
+        return Err(CloudError::InvalidArtifactName);
+    }
+    let file = format!("{}/{}", resolved.cache_dir, resolved.file_name);
+    ensure_cached(&file, &resolved.url, resolved.checksum.as_ref()).await?;
+    let sha256 = file_sha256(&file).await?;
+
+    Ok(InstalledPlugin {
+        name: source.name(),
+        source,
+        version: resolved.version,
+        url: Some(resolved.url),
+        file,
+        sha256,
+    })
+}
+
+fn lockfile_path(instance: &Instance) -> String {
+    format!("{}/{}", instance.directory(), LOCKFILE)
+}
+
+pub fn write_lockfile(instance: &Instance) -> Result<(), CloudError> {
+    let lockfile = Lockfile {
+        plugins: instance.plugins.clone(),
+    };
+    let content = serde_json::to_string_pretty(&lockfile).map_err(|_| CloudError::JSONError)?;
+    write(lockfile_path(instance), content).map_err(|_| CloudError::FileError)
+}
+
+fn read_lockfile(instance: &Instance) -> Result<Option<Lockfile>, CloudError> {
+    match read_to_string(lockfile_path(instance)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|_| CloudError::JSONError),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Lockfiles may be written by hand, their cache paths must stay inside the plugin cache.
+fn is_cache_path(file: &str) -> bool {
+    let path = std::path::Path::new(file);
+    path.starts_with(PLUGINS_DIR)
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Makes the content folder hold exactly the jars in the lockfile, downloading missing ones.
+///
+/// Jars at the top of the folder not in the lockfile are removed, plugin data folders are kept.
+pub async fn sync_plugins(instance: &mut Instance) -> Result<SyncReport, CloudError> {
+    let mut report = SyncReport {
+        linked: Vec::new(),
+        removed: Vec::new(),
+    };
+    let Some(content_dir) = build_loader(&instance.loader).content_dir() else {
+        return Ok(report);
+    };
+    let Some(lockfile) = read_lockfile(instance)? else {
+        return Ok(report);
+    };
+
+    for plugin in &lockfile.plugins {
+        if !is_cache_path(&plugin.file) {
+            return Err(CloudError::InvalidArtifactName);
+        }
+        let checksum = Checksum::Sha256(plugin.sha256.clone());
+        match &plugin.url {
+            Some(url) => ensure_cached(&plugin.file, url, Some(&checksum)).await?,
+            None if std::path::Path::new(&plugin.file).exists() => {}
+            None => {
+                eprintln!("{} is gone from the cache and cannot be downloaded again", plugin.file);
+                return Err(CloudError::FileError);
+            }
+        }
+        if !file_sha256(&plugin.file).await?.eq_ignore_ascii_case(&plugin.sha256) {
+            eprintln!("Cached {} does not match the lockfile", plugin.file);
+            return Err(CloudError::ChecksumMismatch);
+        }
+        link_plugin(instance, content_dir, &plugin.file)?;
+        report.linked.push(plugin.name.clone());
+    }
+
+    let locked = lockfile
+        .plugins
+        .iter()
+        .map(|plugin| link_path(instance, content_dir, &plugin.file))
+        .collect::<Vec<_>>();
+    let folder = format!("{}/{}", instance.directory(), content_dir);
+    for entry in read_dir(&folder).into_iter().flatten().filter_map(Result::ok) {
+        let path = format!("{}/{}", folder, entry.file_name().to_string_lossy());
+        let is_jar = path.ends_with(".jar")
+            && symlink_metadata(&path).is_ok_and(|metadata| !metadata.is_dir());
+        if is_jar && !locked.contains(&path) {
+            remove_file(&path).map_err(|_| CloudError::FileError)?;
+            report.removed.push(path);
+        }
+    }
+
+    instance.plugins = lockfile.plugins;
+    Ok(report)
+}
+
+fn plugin_error(error: CloudError) -> (StatusCode, &'static str) {
+    match error {
+        CloudError::Unsupported => (
+            StatusCode::BAD_REQUEST,
+            "This loader does not support plugins from this source",
+        ),
+        CloudError::Incompatible => (
+            StatusCode::CONFLICT,
+            "No version of this plugin supports the instance's loader and Minecraft version",
+        ),
+        CloudError::NoArtifactForVersion => (StatusCode::NOT_FOUND, "Could not find this plugin version"),
+        CloudError::InvalidArtifactName => (StatusCode::BAD_REQUEST, "Invalid plugin file name"),
+        CloudError::ChecksumMismatch => (StatusCode::BAD_GATEWAY, "Downloaded plugin failed verification"),
+        _ => (StatusCode::BAD_GATEWAY, "Could not download the plugin"),
+    }
+}
+
+pub async fn list_plugins(
+    State(state): State<AppState>,
+    Path(server_id): Path<String>,
+) -> impl IntoResponse {
+    let instance_opt = {
+        let guard = state.daemon.lock().await;
+        guard.get_instance(&server_id).await.clone()
+    };
+
+    if let Some(inst_arc) = instance_opt {
+        Json(inst_arc.lock().await.plugins.clone()).into_response()
+    } else {
+        (StatusCode::NOT_FOUND, "Could not find this instance").into_response()
+    }
+}
+
+pub async fn add_plugin(
+    State(state): State<AppState>,
+    Path(server_id): Path<String>,
+    Json(source): Json<PluginSource>,
+) -> impl IntoResponse {
+    let instance_opt = {
+        let guard = state.daemon.lock().await;
+        guard.get_instance(&server_id).await.clone()
+    };
+    let Some(inst_arc) = instance_opt else {
+        return (StatusCode::NOT_FOUND, "Could not find this instance").into_response();
+    };
+
+    let name = source.name();
+    let instance = inst_arc.lock().await.clone();
+    if instance.plugins.iter().any(|p| p.name == name) {
+        return (StatusCode::CONFLICT, "This plugin is already installed").into_response();
+    }
+
+    let plugin = match fetch_plugin(&instance, source).await {
+        Ok(plugin) => plugin,
+        Err(e) => return plugin_error(e).into_response(),
+    };
+
+    let mut instance = inst_arc.lock().await;
+    if instance.plugins.iter().any(|p| p.name == name) {
+        return (StatusCode::CONFLICT, "This plugin is already installed").into_response();
+    }
+    let content_dir = build_loader(&instance.loader).content_dir().unwrap_or(PLUGINS_DIR);
+    if link_plugin(&instance, content_dir, &plugin.file).is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Could not install the plugin").into_response();
+    }
+    instance.plugins.push(plugin.clone());
+    if write_lockfile(&instance).is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Could not update the lockfile").into_response();
+    }
+    (StatusCode::CREATED, Json(plugin)).into_response()
+}
+
+pub async fn remove_plugin(
+    State(state): State<AppState>,
+    Path((server_id, name)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let instance_opt = {
+        let guard = state.daemon.lock().await;
+        guard.get_instance(&server_id).await.clone()
+    };
+    let Some(inst_arc) = instance_opt else {
+        return (StatusCode::NOT_FOUND, "Could not find this instance").into_response();
+    };
+
+    let mut instance = inst_arc.lock().await;
+    let Some(index) = instance.plugins.iter().position(|p| p.name == name) else {
+        return (StatusCode::NOT_FOUND, "This plugin is not installed").into_response();
+    };
+
+    let content_dir = build_loader(&instance.loader).content_dir().unwrap_or(PLUGINS_DIR);
+    let link = link_path(&instance, content_dir, &instance.plugins[index].file);
+    if symlink_metadata(&link).is_ok() && remove_file(&link).is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Could not remove the plugin").into_response();
+    }
+    instance.plugins.remove(index);
+    if write_lockfile(&instance).is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Could not update the lockfile").into_response();
+    }
+    (StatusCode::OK, "Plugin removed").into_response()
+}
+
+pub async fn sync_instance_plugins(
+    State(state): State<AppState>,
+    Path(server_id): Path<String>,
+) -> impl IntoResponse {
+    let instance_opt = {
+        let guard = state.daemon.lock().await;
+        guard.get_instance(&server_id).await.clone()
+    };
+    let Some(inst_arc) = instance_opt else {
+        return (StatusCode::NOT_FOUND, "Could not find this instance").into_response();
+    };
+
+    let mut instance = inst_arc.lock().await;
+    match sync_plugins(&mut instance).await {
+        Ok(report) => Json(report).into_response(),
+        Err(CloudError::JSONError) => {
+            (StatusCode::BAD_REQUEST, "The lockfile could not be read").into_response()
+        }
+        Err(e) => plugin_error(e).into_response(),
+    }
+}
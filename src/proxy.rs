@@ -0,0 +1,129 @@
+use crate::Daemon;
+use crate::errors::CloudError;
+use crate::instance::{Instance, InstanceRole};
+use crate::screen_manager::send_command;
+use std::fs::{read_to_string, write};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub const VELOCITY_CONFIG: &str = "velocity.toml";
+
+/// Written when a proxy is registered, Velocity fills in every other setting with its defaults.
+pub fn write_initial_config(instance: &Instance) -> Result<(), CloudError> {
+    let content = format!(
+        "bind = \"0.0.0.0:{}\"\nshow-max-players = {}\n\n[servers]\ntry = []\n",
+        instance.port, instance.max_player
+    );
+    write(format!("{}/{}", instance.directory(), VELOCITY_CONFIG), content)
+        .map_err(|_| CloudError::FileError)
+}
+
+/// Whether a proxy routes players to a backend: proxies in a group only list its backends.
+pub fn lists(proxy: &Instance, backend: &Instance) -> bool {
+    proxy.role == InstanceRole::Proxy
+        && backend.role == InstanceRole::Backend
+        && (proxy.group.is_none() || proxy.group == backend.group)
+}
+
+/// Whether a backend is reached through a proxy: it opted in, or a managed proxy lists it.
+pub async fn is_proxied(daemon: &Arc<Mutex<Daemon>>, inst_arc: &Arc<Mutex<Instance>>) -> bool {
+    let backend = inst_arc.lock().await.clone();
+    if backend.behind_proxy {
+        return true;
+    }
+    let guard = daemon.lock().await;
+    for inst in &guard.server_list {
+        if Arc::ptr_eq(inst, inst_arc) {
+            continue;
+        }
+        if lists(&*inst.lock().await, &backend) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Points every proxy at the started backends it lists, then asks running proxies to reload.
+pub async fn sync_proxies(daemon: Arc<Mutex<Daemon>>) {
+    let mut backends = Vec::new();
+    let mut proxies = Vec::new();
+    {
+        let guard = daemon.lock().await;
+        for inst in &guard.server_list {
+            let inst_guard = inst.lock().await;
+            match inst_guard.role {
+                InstanceRole::Backend if inst_guard.started => backends.push(inst_guard.clone()),
+                InstanceRole::Backend => {}
+                InstanceRole::Proxy => proxies.push(inst_guard.clone()),
+            }
+        }
+    }
+
+    for proxy in proxies {
+        let mut listed = backends
+            .iter()
+            .filter(|backend| lists(&proxy, backend))
+            .map(|backend| (backend.server_id.clone(), backend.port))
+            .collect::<Vec<_>>();
+        listed.sort();
+
+        let path = format!("{}/{}", proxy.directory(), VELOCITY_CONFIG);
+        if let Err(e) = rewrite_servers(Path::new(&path), &listed) {
+            eprintln!("Failed to update {}: {:?}", path, e);
+            continue;
+        }
+        if proxy.started && send_command(&proxy.server_id, "velocity reload").is_err() {
+            eprintln!("Could not reload proxy {}", proxy.server_id);
+        }
+    }
+}
+
+fn rewrite_servers(path: &Path, backends: &[(String, u16)]) -> Result<(), CloudError> {
+    let content = read_to_string(path).map_err(|_| CloudError::FileError)?;
+
+    let mut section = String::from("[servers]\n");
+    for (server_id, port) in backends {
+        section.push_str(&format!("\"{}\" = \"127.0.0.1:{}\"\n", server_id, port));
+    }
+    let tried = backends
+        .iter()
+        .map(|(server_id, _)| format!("\"{}\"", server_id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    section.push_str(&format!("try = [{}]\n", tried));
+
+    let mut output = String::new();
+    let mut in_servers = false;
+    let mut replaced = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if is_table_header(trimmed) {
+            in_servers = trimmed == "[servers]";
+            if in_servers {
+                output.push_str(&section);
+                output.push('\n');
+                replaced = true;
+                continue;
+            }
+        }
+        if !in_servers {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    if !replaced {
+        output.push('\n');
+        output.push_str(&section);
+    }
+
+    write(path, output).map_err(|_| CloudError::FileError)
+}
+
+fn is_table_header(line: &str) -> bool {
+    line.starts_with('[')
+        && line.ends_with(']')
+        && !line.starts_with("[[")
+        && !line.contains(['"', ','])
+}
@@ -0,0 +1,130 @@
+use crate::config;
+use crate::errors::CloudError;
+use std::fs::{File, create_dir_all, read_to_string, write};
+use std::io::Read;
+use std::path::Path;
+
+const SECRET_PATH: &str = "forwarding.secret";
+
+/// The secret proxies and backends share for modern forwarding, generated once per daemon.
+pub fn forwarding_secret() -> Result<String, CloudError> {
+    if let Some(secret) = &config::get().proxy.forwarding_secret {
+        return Ok(secret.clone());
+    }
+    if let Ok(secret) = read_to_string(SECRET_PATH) {
+        return Ok(secret.trim().to_string());
+    }
+
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .map_err(|_| CloudError::FileError)?;
+    let secret = bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    write(SECRET_PATH, &secret).map_err(|_| CloudError::FileError)?;
+    Ok(secret)
+}
+
+fn read_lines(path: &str) -> Vec<String> {
+    read_to_string(path)
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn write_lines(path: &str, lines: &[String]) -> Result<(), CloudError> {
+    if let Some(parent) = Path::new(path).parent() {
+        create_dir_all(parent).map_err(|_| CloudError::FileError)?;
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    write(path, content).map_err(|_| CloudError::FileError)
+}
+
+/// Sets `key=value` pairs in a `.properties` file, keeping every other line.
+pub fn patch_properties(path: &str, values: &[(&str, String)]) -> Result<(), CloudError> {
+    let mut lines = read_lines(path);
+    for (key, value) in values {
+        let line = format!("{}={}", key, value);
+        match lines
+            .iter()
+            .position(|l| l.split('=').next().map(str::trim) == Some(*key))
+        {
+            Some(index) => lines[index] = line,
+            None => lines.push(line),
+        }
+    }
+    write_lines(path, &lines)
+}
+
+/// Sets root-level keys of a TOML file, new ones go before the first table.
+pub fn patch_toml(path: &str, values: &[(&str, String)]) -> Result<(), CloudError> {
+    let mut lines = read_lines(path);
+    for (key, value) in values {
+        let first_table = lines
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .unwrap_or(lines.len());
+        let line = format!("{} = {}", key, value);
+        match lines[..first_table]
+            .iter()
+            .position(|l| l.split('=').next().map(str::trim) == Some(*key))
+        {
+            Some(index) => lines[index] = line,
+            None => lines.insert(first_table, line),
+        }
+    }
+    write_lines(path, &lines)
+}
+
+/// Sets nested keys of a YAML file as written by Bukkit and Paper, indented by two spaces.
+///
+/// Only block mappings are understood, which is all these files use for the keys we set.
+pub fn patch_yaml(path: &str, values: &[(&[&str], String)]) -> Result<(), CloudError> {
+    let mut lines = read_lines(path);
+    for (keys, value) in values {
+        set_yaml_value(&mut lines, keys, value);
+    }
+    write_lines(path, &lines)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn set_yaml_value(lines: &mut Vec<String>, keys: &[&str], value: &str) {
+    let (mut start, mut end) = (0, lines.len());
+    for (depth, key) in keys.iter().enumerate() {
+        let prefix = format!("{}{}:", "  ".repeat(depth), key);
+        let found = (start..end).find(|&i| {
+            lines[i]
+                .strip_prefix(&prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+        });
+
+        let Some(index) = found else {
+            let missing = keys[depth..].iter().enumerate().map(|(offset, key)| {
+                let indent = "  ".repeat(depth + offset);
+                if depth + offset + 1 == keys.len() {
+                    format!("{}{}: {}", indent, key, value)
+                } else {
+                    format!("{}{}:", indent, key)
+                }
+            });
+            lines.splice(end..end, missing.collect::<Vec<_>>());
+            return;
+        };
+
+        if depth + 1 == keys.len() {
+            lines[index] = format!("{} {}", prefix, value);
+            return;
+        }
+
+        // The mapping below this key runs until a line is indented at most as far as the key.
+        start = index + 1;
+        end = (start..end)
+            .find(|&i| {
+                let trimmed = lines[i].trim_start();
+                !trimmed.is_empty() && !trimmed.starts_with('#') && indentation(&lines[i]) <= depth * 2
+            })
+            .unwrap_or(end);
+    }
+}
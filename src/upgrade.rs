@@ -0,0 +1,228 @@
+use crate::errors::CloudError;
+use crate::heartbeat::now;
+use crate::installs::ensure_loader;
+use crate::instance::{Instance, start_instance};
+use crate::loader::build_loader;
+use crate::proxy::sync_proxies;
+use crate::screen_manager::{is_screen_running, stop_screen};
+use crate::{AppState, Daemon};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{Instant, sleep};
+
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_HEALTH_TIMEOUT_SECS: u64 = 180;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize)]
+pub struct UpgradeRequest {
+    #[serde(default)]
+    server_ids: Vec<String>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    health_timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum UpgradeStatus {
+    UpToDate,
+    Unsupported,
+    Upgraded,
+    RolledBack,
+    Failed,
+}
+
+#[derive(Serialize)]
+struct UpgradeResult {
+    server_id: String,
+    from: Option<String>,
+    to: Option<String>,
+    status: UpgradeStatus,
+}
+
+async fn select_instances(
+    daemon: &Arc<Mutex<Daemon>>,
+    request: &UpgradeRequest,
+) -> Vec<Arc<Mutex<Instance>>> {
+    let guard = daemon.lock().await;
+    let mut selected = Vec::new();
+    for inst in &guard.server_list {
+        let inst_guard = inst.lock().await;
+        let in_group = request.group.is_some() && inst_guard.group == request.group;
+        if in_group || request.server_ids.contains(&inst_guard.server_id) {
+            selected.push(inst.clone());
+        }
+    }
+    selected
+}
+
+async fn wait_until_stopped(server_id: &str) -> Result<(), CloudError> {
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    while is_screen_running(server_id) {
+        if Instant::now() >= deadline {
+            return Err(CloudError::ScreenError);
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+async fn shutdown(inst_arc: &Arc<Mutex<Instance>>) -> Result<(), CloudError> {
+    let server_id = inst_arc.lock().await.server_id.clone();
+    if is_screen_running(&server_id) {
+        stop_screen(inst_arc.clone()).await?;
+        wait_until_stopped(&server_id).await?;
+    }
+    inst_arc.lock().await.started = false;
+    Ok(())
+}
+
+/// Healthy means the server sent a heartbeat after `since`, like it does once fully started.
+async fn wait_until_healthy(inst_arc: &Arc<Mutex<Instance>>, since: u64, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if inst_arc.lock().await.last_heartbeat >= since {
+            return true;
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+    false
+}
+
+async fn restart(
+    daemon: &Arc<Mutex<Daemon>>,
+    inst_arc: &Arc<Mutex<Instance>>,
+) -> Result<(), CloudError> {
+    let (status, _) = start_instance(daemon, inst_arc.clone()).await;
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(CloudError::ScreenError)
+    }
+}
+
+/// Moves one instance to the newest build by pinning it, pinning it back if it does not come back.
+///
+/// Each build has its own cached artifact, so other instances keep running the build they had.
+async fn upgrade_instance(
+    daemon: &Arc<Mutex<Daemon>>,
+    inst_arc: &Arc<Mutex<Instance>>,
+    health_timeout: Duration,
+) -> UpgradeResult {
+    let (server_id, loader_config, running_build, was_started) = {
+        let guard = inst_arc.lock().await;
+        (
+            guard.server_id.clone(),
+            guard.loader.clone(),
+            guard.loader_build.clone(),
+            guard.started,
+        )
+    };
+    let loader = build_loader(&loader_config);
+    let mut result = UpgradeResult {
+        server_id,
+        from: running_build.clone().or_else(|| loader.installed_build()),
+        to: None,
+        status: UpgradeStatus::Failed,
+    };
+
+    let latest = match build_loader(&loader_config.unpinned()).latest_build().await {
+        Ok(Some(latest)) => latest,
+        Ok(None) => {
+            result.status = UpgradeStatus::Unsupported;
+            return result;
+        }
+        Err(e) => {
+            eprintln!("Could not check {} for a newer build: {:?}", result.server_id, e);
+            return result;
+        }
+    };
+    result.to = Some(latest.clone());
+    if result.from.as_deref() == Some(latest.as_str()) {
+        result.status = UpgradeStatus::UpToDate;
+        return result;
+    }
+    let Some(target_config) = loader_config.pinned(&latest) else {
+        result.status = UpgradeStatus::Unsupported;
+        return result;
+    };
+
+    // Fetched before stopping anything, a failed download leaves the instance running as it was.
+    let target = build_loader(&target_config);
+    if let Err(e) = ensure_loader(target.as_ref()).await {
+        eprintln!("Could not download build {} for {}: {:?}", latest, result.server_id, e);
+        return result;
+    }
+
+    println!("Upgrading {} to build {}", result.server_id, latest);
+    if was_started && shutdown(inst_arc).await.is_err() {
+        eprintln!("Could not stop {}, leaving it untouched", result.server_id);
+        return result;
+    }
+    if was_started {
+        sync_proxies(daemon.clone()).await;
+    }
+    {
+        let mut guard = inst_arc.lock().await;
+        guard.loader = target_config;
+        guard.loader_build = target.installed_build();
+    }
+
+    if !was_started {
+        result.status = UpgradeStatus::Upgraded;
+        return result;
+    }
+
+    let since = now();
+    let healthy = restart(daemon, inst_arc).await.is_ok()
+        && wait_until_healthy(inst_arc, since, health_timeout).await;
+    if healthy {
+        sync_proxies(daemon.clone()).await;
+        result.status = UpgradeStatus::Upgraded;
+        return result;
+    }
+
+    eprintln!("{} did not come back on build {}, rolling back", result.server_id, latest);
+    let _ = shutdown(inst_arc).await;
+    {
+        let mut guard = inst_arc.lock().await;
+        guard.loader = loader_config;
+        guard.loader_build = running_build;
+    }
+    result.status = match restart(daemon, inst_arc).await {
+        Ok(()) => UpgradeStatus::RolledBack,
+        Err(_) => UpgradeStatus::Failed,
+    };
+    sync_proxies(daemon.clone()).await;
+    result
+}
+
+pub async fn upgrade_instances(
+    State(state): State<AppState>,
+    Json(request): Json<UpgradeRequest>,
+) -> impl IntoResponse {
+    if request.server_ids.is_empty() && request.group.is_none() {
+        return (StatusCode::BAD_REQUEST, "Select instances by server_ids or group").into_response();
+    }
+
+    let health_timeout = Duration::from_secs(
+        request
+            .health_timeout_secs
+            .unwrap_or(DEFAULT_HEALTH_TIMEOUT_SECS),
+    );
+
+    let mut results = Vec::new();
+    for inst_arc in select_instances(&state.daemon, &request).await {
+        results.push(upgrade_instance(&state.daemon, &inst_arc, health_timeout).await);
+    }
+
+    Json(results).into_response()
+}